@@ -0,0 +1,44 @@
+//! Measure the time taken by a frame of a scene with thousands of sleeping tasks.
+//!
+//! Run it with "cargo bench --bench scheduler".
+use pmd3d_script_runner::{ClockMode, Input, Logic, GAME_FRAMERATE};
+use std::time::Instant;
+
+const TASK_COUNT: usize = 5000;
+const FRAME_COUNT: usize = 600;
+
+fn main() {
+    // each task sleep between 0.5 and 5 seconds, so only a few are woken at each frame
+    let script = format!(
+        "
+        for i = 1, {} do
+            yammy_fork(function()
+                while true do
+                    TASK:Sleep(TimeSec(0.5 + (i % 10) * 0.5))
+                end
+            end)
+        end",
+        TASK_COUNT
+    );
+    let mut logic = Logic::new(&script).unwrap();
+    logic.set_clock_mode(ClockMode::Fixed(GAME_FRAMERATE));
+    let frame_time = 1.0 / GAME_FRAMERATE.get() as f64;
+
+    // start every task
+    logic.execute(Input::new(frame_time)).unwrap();
+    logic.get_and_clear_updates();
+
+    let start = Instant::now();
+    for _ in 0..FRAME_COUNT {
+        logic.execute(Input::new(frame_time)).unwrap();
+        logic.get_and_clear_updates();
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{} sleeping tasks: {} frames in {:?}, {:?} per frame",
+        TASK_COUNT,
+        FRAME_COUNT,
+        elapsed,
+        elapsed / FRAME_COUNT as u32
+    );
+}
@@ -0,0 +1,124 @@
+use crate::gamedata::{Easing, Interpolate, Time, Tween, Vec2_f64};
+
+/// What the scene is looked from: the point at the center of the screen, the zoom and the rotation of the view
+#[derive(Debug)]
+pub struct Camera {
+    /// The point at the center of the screen
+    pub focus: Vec2_f64,
+    /// How much the view is enlarged, 1 by default
+    pub zoom: f64,
+    /// The rotation of the view, in radians. When positive, the scene appear turned clockwise.
+    pub angle: f64,
+    /// The character (by it's id) the focus stay on, if any
+    pub follow: Option<String>,
+    focus_move: Option<Tween<Vec2_f64>>,
+    zoom_change: Option<Tween<f64>>,
+    rotation: Option<Tween<f64>>,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            focus: Vec2_f64::new(0.0, 0.0),
+            zoom: 1.0,
+            angle: 0.0,
+            follow: None,
+            focus_move: None,
+            zoom_change: None,
+            rotation: None,
+        }
+    }
+}
+
+/// Start the tween, or set the value immediately for a null duration
+fn start_tween<T: Interpolate + Clone>(
+    value: &mut T,
+    tween: &mut Option<Tween<T>>,
+    target: T,
+    duration: Time,
+    easing: Easing,
+) {
+    let new_tween = Tween::new(value.clone(), target, duration, easing);
+    if new_tween.is_finished() {
+        *value = new_tween.value();
+        *tween = None;
+    } else {
+        *tween = Some(new_tween);
+    };
+}
+
+/// Advance the tween, writing its value. Remove it once finished.
+fn advance_tween<T: Interpolate + Clone>(value: &mut T, tween: &mut Option<Tween<T>>, time: Time) {
+    if let Some(ongoing) = tween {
+        ongoing.time_spent(time);
+        *value = ongoing.value();
+        if ongoing.is_finished() {
+            *tween = None;
+        };
+    };
+}
+
+impl Camera {
+    /// Move the focus to the position in duration (immediately if null), and stop following a character
+    pub fn move_focus(&mut self, position: Vec2_f64, duration: Time, easing: Easing) {
+        self.follow = None;
+        start_tween(
+            &mut self.focus,
+            &mut self.focus_move,
+            position,
+            duration,
+            easing,
+        );
+    }
+
+    /// Keep the focus on the character (by it's id), or stop following with ``None``
+    pub fn follow(&mut self, charid: Option<String>) {
+        self.focus_move = None;
+        self.follow = charid;
+    }
+
+    /// Change the zoom in duration, immediately if null
+    pub fn zoom_to(&mut self, zoom: f64, duration: Time, easing: Easing) {
+        start_tween(
+            &mut self.zoom,
+            &mut self.zoom_change,
+            zoom,
+            duration,
+            easing,
+        );
+    }
+
+    /// Rotate the view to the angle (in radians) in duration, immediately if null
+    pub fn rotate_to(&mut self, angle: f64, duration: Time, easing: Easing) {
+        start_tween(&mut self.angle, &mut self.rotation, angle, duration, easing);
+    }
+
+    /// Return ``true`` if the focus, the zoom or the angle is changing. Following a character doesn't count.
+    pub fn is_moving(&self) -> bool {
+        self.focus_move.is_some() || self.zoom_change.is_some() || self.rotation.is_some()
+    }
+
+    pub fn time_spent(&mut self, time: Time) {
+        advance_tween(&mut self.focus, &mut self.focus_move, time);
+        advance_tween(&mut self.zoom, &mut self.zoom_change, time);
+        advance_tween(&mut self.angle, &mut self.rotation, time);
+    }
+}
+
+#[test]
+fn test_camera() {
+    let mut camera = Camera::default();
+    camera.follow(Some("HERO".into()));
+    camera.move_focus(Vec2_f64::new(2.0, 0.0), Time::new(1.0), Easing::Linear);
+    assert_eq!(camera.follow, None);
+    camera.zoom_to(3.0, Time::new(2.0), Easing::Linear);
+    camera.rotate_to(1.0, Time::new(0.0), Easing::Linear);
+    assert_eq!(camera.angle, 1.0);
+    camera.time_spent(Time::new(1.0));
+    assert_eq!(camera.focus, Vec2_f64::new(2.0, 0.0));
+    assert_eq!(camera.zoom, 2.0);
+    assert!(camera.is_moving());
+    camera.time_spent(Time::new(1.0));
+    assert_eq!(camera.zoom, 3.0);
+    assert!(!camera.is_moving());
+}
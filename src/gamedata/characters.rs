@@ -1,23 +1,120 @@
-use crate::gamedata::{Speed, Time, Vec2_f64, Vec3_f64};
+use crate::gamedata::{
+    angle_difference, Easing, Interpolate, Motion, Speed, Time, Tween, Vec2_f64, Vec3_f64,
+};
+
+/// How a character move
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveKind {
+    /// With the walk animation, facing where it go
+    Walk,
+    /// With the run animation, facing where it go
+    Run,
+    /// Keeping its animation and direction
+    Slide,
+}
+
+impl MoveKind {
+    /// The names used by the scripts, in the ``MOVE`` table
+    pub const ALL: [(&'static str, MoveKind); 3] = [
+        ("WALK", Self::Walk),
+        ("RUN", Self::Run),
+        ("SLIDE", Self::Slide),
+    ];
+
+    /// Return ``true`` if the character play an animation for this movement, and come back to idle at its end
+    pub fn is_animated(self) -> bool {
+        self != Self::Slide
+    }
+}
 
 #[derive(Debug)]
-struct WalkTo {
-    destination: Vec2_f64,
-    speed: Speed,
+struct Movement {
+    /// The points to go through, in order, starting with the position the character started from
+    path: Vec<Vec2_f64>,
+    /// The distance travelled along the path
+    travelled: Tween<f64>,
+    kind: MoveKind,
+}
+
+impl Movement {
+    /// Return the point of the path at the distance from its start, and the direction (in radians) of the part of
+    /// the path it is on, if it isn't null
+    fn point_at(&self, distance: f64) -> (Vec2_f64, Option<f64>) {
+        let mut left = distance;
+        let mut last = (self.path[0], None);
+        for segment in self.path.windows(2) {
+            let direction = segment[1] - segment[0];
+            let lenght = segment[1].distance(&segment[0]);
+            if lenght == 0.0 {
+                continue;
+            };
+            if left < lenght {
+                let ratio = left / lenght;
+                let point = segment[0] + Vec2_f64::new(direction.x * ratio, direction.y * ratio);
+                return (point, Some(direction.angle()));
+            };
+            left -= lenght;
+            last = (segment[1], Some(direction.angle()));
+        }
+        last
+    }
 }
 
 #[derive(Debug)]
 enum OngoingMovement {
     None,
-    WalkTo(WalkTo),
+    Move(Movement),
+}
+
+/// A progressive change of the height of a character
+#[derive(Debug)]
+enum VerticalMovement {
+    /// A parabolic jump, starting and ending at the height base, peaking height above it
+    Jump { base: f64, height: f64 },
+    /// A change of height, following the easing of the progress
+    HeightTo { from: f64, to: f64 },
+}
+
+#[derive(Debug)]
+struct Vertical {
+    movement: VerticalMovement,
+    /// The progress of the movement, from 0 to 1
+    progress: Tween<f64>,
+}
+
+impl Vertical {
+    /// The height at the current time
+    fn height(&self) -> f64 {
+        let progress = self.progress.value();
+        match self.movement {
+            VerticalMovement::Jump { base, height } => {
+                base + 4.0 * height * progress * (1.0 - progress)
+            }
+            VerticalMovement::HeightTo { from, to } => from.interpolate(&to, progress),
+        }
+    }
+}
+
+/// A progressive change of the direction of a character
+#[derive(Debug)]
+struct Turn {
+    /// The angle to reach, in radians
+    target: f64,
+    /// The angle turned each second, in radians
+    angular_speed: f64,
 }
 
 #[derive(Debug)]
 pub struct Chara {
     pub position: Vec3_f64,
     ongoing_movement: OngoingMovement,
+    turn: Option<Turn>,
+    vertical: Option<Vertical>,
+    /// The direction the character face, in radians. 0 face the right, PI/2 the top.
     pub angle: f64,
-    _actor: String, //TODO: change with another data structure
+    /// The animation played, when not walking
+    pub motion: Motion,
+    pub actor: String, //TODO: change with another data structure
 }
 
 impl Chara {
@@ -25,66 +122,231 @@ impl Chara {
         Self {
             position: Vec3_f64::default(),
             ongoing_movement: OngoingMovement::None,
+            turn: None,
+            vertical: None,
             angle: 0.0,
-            _actor: actor,
+            motion: Motion::idle(),
+            actor,
         }
     }
 
     pub fn abort_ongoing_movement(&mut self) {
-        let mut should_reinitialize = false;
-        match self.ongoing_movement {
-            OngoingMovement::None => (),
-            OngoingMovement::WalkTo(_) => should_reinitialize = true,
-        };
-        if should_reinitialize {
-            self.ongoing_movement = OngoingMovement::None;
-        }
+        self.ongoing_movement = OngoingMovement::None;
     }
 
     pub fn set_position(&mut self, position: Vec3_f64) {
         self.abort_ongoing_movement(); //verified in game with WalkTo
+        self.vertical = None;
         self.position = position;
     }
 
+    /// Face the direction immediately, stopping the turn in progress
+    pub fn set_angle(&mut self, angle: f64) {
+        self.turn = None;
+        self.angle = angle;
+    }
+
+    /// Turn progressively to face the direction, the shortest way, in duration
+    pub fn turn_to(&mut self, angle: f64, duration: Time) {
+        let difference = angle_difference(self.angle, angle);
+        if duration.get_time() <= 0.0 || difference == 0.0 {
+            self.set_angle(angle);
+        } else {
+            self.turn = Some(Turn {
+                target: self.angle + difference,
+                angular_speed: difference.abs() / duration.get_time(),
+            });
+        };
+    }
+
+    /// Jump in place (it can move horizontally at the same time), up to height above its current height, landing after duration
+    pub fn jump(&mut self, height: f64, duration: Time) {
+        self.start_vertical(
+            VerticalMovement::Jump {
+                base: self.position.z,
+                height,
+            },
+            duration,
+            Easing::Linear,
+        );
+    }
+
+    /// Change the height (the z coordinate) in duration, like to fall. Immediate for a null duration.
+    pub fn set_height(&mut self, height: f64, duration: Time, easing: Easing) {
+        self.start_vertical(
+            VerticalMovement::HeightTo {
+                from: self.position.z,
+                to: height,
+            },
+            duration,
+            easing,
+        );
+    }
+
+    fn start_vertical(&mut self, movement: VerticalMovement, duration: Time, easing: Easing) {
+        let vertical = Vertical {
+            movement,
+            progress: Tween::new(0.0, 1.0, duration, easing),
+        };
+        if vertical.progress.is_finished() {
+            self.position.z = vertical.height();
+            self.vertical = None;
+        } else {
+            self.vertical = Some(vertical);
+        };
+    }
+
+    /// Return ``true`` if the height of the character is changing, like when jumping
+    pub fn is_moving_vertically(&self) -> bool {
+        self.vertical.is_some()
+    }
+
+    /// Return ``true`` if the character is turning
+    pub fn is_turning(&self) -> bool {
+        self.turn.is_some()
+    }
+
     pub fn walk_to(&mut self, destination: Vec2_f64, speed: Speed) {
-        self.abort_ongoing_movement();
-        self.ongoing_movement = OngoingMovement::WalkTo(WalkTo { destination, speed })
+        self.move_along(vec![destination], speed, MoveKind::Walk, Easing::Linear);
     }
 
-    pub fn time_spent(&mut self, time: Time) -> bool {
-        let moved = match &self.ongoing_movement {
-            OngoingMovement::None => false,
-            OngoingMovement::WalkTo(walk_to) => {
-                // Distance between the actual posititon and the destination
-                let distance_to_target = walk_to.destination.distance(&self.position.to_vec2());
-                let distance_able_to_walk = walk_to.speed.0 * time.0;
-                let (walked_to, finished) = if distance_able_to_walk < distance_to_target {
-                    let vector = (walk_to.destination - self.position.to_vec2()).normalize();
-                    self.angle = if vector.x >= 0.0 {
-                        f64::atan(vector.y / vector.x)
-                    } else {
-                        f64::atan(vector.y / vector.x) + std::f64::consts::PI
-                    };
-                    (
-                        self.position.to_vec2()
-                            + Vec2_f64 {
-                                x: vector.x * distance_able_to_walk,
-                                y: vector.y * distance_able_to_walk,
-                            },
-                        true,
-                    )
-                } else {
-                    (walk_to.destination, false)
-                };
-                self.position = walked_to.to_vec3(0.0);
-                finished
-            }
+    /// Go through every point of the path, in order, without stopping. The speed is the average one, the easing
+    /// apply to the whole path.
+    pub fn move_along(
+        &mut self,
+        path: Vec<Vec2_f64>,
+        speed: Speed,
+        kind: MoveKind,
+        easing: Easing,
+    ) {
+        self.abort_ongoing_movement();
+        if kind != MoveKind::Slide {
+            // walking set the direction
+            self.turn = None;
         };
-        if !moved {
-            self.ongoing_movement = OngoingMovement::None;
-            true
+        let mut full_path = vec![self.position.to_vec2()];
+        full_path.extend(path);
+        let lenght: f64 = full_path
+            .windows(2)
+            .map(|segment| segment[1].distance(&segment[0]))
+            .sum();
+        let duration = if lenght == 0.0 {
+            Time::new(0.0)
         } else {
-            false
+            Time::new(lenght / speed.0)
+        };
+        self.ongoing_movement = OngoingMovement::Move(Movement {
+            path: full_path,
+            travelled: Tween::new(0.0, lenght, duration, easing),
+            kind,
+        })
+    }
+
+    /// Return how the character is moving, if it is
+    pub fn moving_kind(&self) -> Option<MoveKind> {
+        match &self.ongoing_movement {
+            OngoingMovement::None => None,
+            OngoingMovement::Move(movement) => Some(movement.kind),
         }
     }
+
+    /// Advance the movement and the turn of the character. Return ``true`` if it isn't moving.
+    pub fn time_spent(&mut self, time: Time) -> bool {
+        if let Some(turn) = &self.turn {
+            let difference = turn.target - self.angle;
+            let able_to_turn = turn.angular_speed * time.get_time();
+            if able_to_turn >= difference.abs() {
+                self.angle = turn.target;
+                self.turn = None;
+            } else {
+                self.angle += able_to_turn.copysign(difference);
+            };
+        };
+        if let Some(vertical) = &mut self.vertical {
+            vertical.progress.time_spent(time);
+            self.position.z = vertical.height();
+            if vertical.progress.is_finished() {
+                self.vertical = None;
+            };
+        };
+        if let OngoingMovement::Move(movement) = &mut self.ongoing_movement {
+            movement.travelled.time_spent(time);
+            let (position, direction) = movement.point_at(movement.travelled.value());
+            if let (Some(direction), true) = (direction, movement.kind != MoveKind::Slide) {
+                self.angle = direction;
+            };
+            self.position = position.to_vec3(self.position.z);
+            if movement.travelled.is_finished() {
+                self.ongoing_movement = OngoingMovement::None;
+            };
+        };
+        self.moving_kind().is_none()
+    }
+}
+#[test]
+fn test_chara_turn() {
+    use std::f64::consts::PI;
+    let mut chara = Chara::new("KIBAGO".into());
+    chara.set_angle(0.25 * PI);
+    // turn through the top, the shortest way
+    chara.turn_to(0.75 * PI, Time::new(1.0));
+    chara.time_spent(Time::new(0.5));
+    assert!((chara.angle - 0.5 * PI).abs() < 1e-9);
+    assert!(chara.is_turning());
+    chara.time_spent(Time::new(0.6));
+    assert_eq!(chara.angle, 0.75 * PI);
+    assert!(!chara.is_turning());
+    chara.turn_to(-PI / 2.0, Time::new(0.0));
+    assert_eq!(chara.angle, -PI / 2.0);
+}
+
+#[test]
+fn test_chara_move_along() {
+    let mut chara = Chara::new("KIBAGO".into());
+    chara.move_along(
+        vec![Vec2_f64::new(1.0, 0.0), Vec2_f64::new(1.0, 2.0)],
+        Speed::new(1.0),
+        MoveKind::Run,
+        Easing::Linear,
+    );
+    // the distance left after the first waypoint is used toward the next one
+    assert!(!chara.time_spent(Time::new(1.5)));
+    assert_eq!(chara.position, Vec3_f64::new(1.0, 0.5, 0.0));
+    assert_eq!(chara.angle, std::f64::consts::FRAC_PI_2);
+    assert_eq!(chara.moving_kind(), Some(MoveKind::Run));
+    assert!(chara.time_spent(Time::new(2.0)));
+    assert_eq!(chara.position, Vec3_f64::new(1.0, 2.0, 0.0));
+    assert_eq!(chara.moving_kind(), None);
+
+    // sliding keep the direction
+    chara.move_along(
+        vec![Vec2_f64::new(0.0, 2.0)],
+        Speed::new(1.0),
+        MoveKind::Slide,
+        Easing::Linear,
+    );
+    chara.time_spent(Time::new(0.5));
+    assert_eq!(chara.position, Vec3_f64::new(0.5, 2.0, 0.0));
+    assert_eq!(chara.angle, std::f64::consts::FRAC_PI_2);
+}
+
+#[test]
+fn test_chara_vertical() {
+    let mut chara = Chara::new("KIBAGO".into());
+    chara.set_position(Vec3_f64::new(0.0, 0.0, 1.0));
+    chara.walk_to(Vec2_f64::new(1.0, 0.0), Speed::new(1.0));
+    chara.jump(2.0, Time::new(1.0));
+    chara.time_spent(Time::new(0.5));
+    assert_eq!(chara.position, Vec3_f64::new(0.5, 0.0, 3.0));
+    assert!(chara.is_moving_vertically());
+    chara.time_spent(Time::new(0.5));
+    // landed where it jumped from
+    assert_eq!(chara.position, Vec3_f64::new(1.0, 0.0, 1.0));
+    assert!(!chara.is_moving_vertically());
+    chara.set_height(0.0, Time::new(2.0), Easing::Linear);
+    chara.time_spent(Time::new(1.0));
+    assert_eq!(chara.position.z, 0.5);
+    chara.set_height(4.0, Time::new(0.0), Easing::Linear);
+    assert_eq!(chara.position.z, 4.0);
+    assert!(!chara.is_moving_vertically());
 }
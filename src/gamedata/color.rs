@@ -1,4 +1,7 @@
-#[derive(Debug, Clone, PartialEq)]
+use mlua::{FromLua, UserData};
+
+/// A color, with each component between 0 and 1
+#[derive(Debug, Clone, PartialEq, FromLua)]
 pub struct Color {
     pub r: f32,
     pub g: f32,
@@ -13,6 +16,10 @@ impl Default for Color {
 }
 
 impl Color {
+    pub fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self { r, g, b, a }
+    }
+
     pub fn transparent() -> Self {
         Self {
             r: 0.0,
@@ -30,4 +37,20 @@ impl Color {
             a: 1.0,
         }
     }
+
+    pub fn white() -> Self {
+        Self {
+            r: 1.0,
+            g: 1.0,
+            b: 1.0,
+            a: 1.0,
+        }
+    }
+
+    /// The same color, with another alpha
+    pub fn with_alpha(&self, a: f32) -> Self {
+        Self { a, ..self.clone() }
+    }
 }
+
+impl UserData for Color {}
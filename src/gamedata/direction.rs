@@ -0,0 +1,33 @@
+use std::f64::consts::PI;
+
+/// The direction constants of the scripts, with their angle in degree. 0 face the right of the screen, 90 the top.
+pub const DIRECTIONS: [(&str, f64); 8] = [
+    ("RIGHT", 0.0),
+    ("UP_RIGHT", 45.0),
+    ("UP", 90.0),
+    ("UP_LEFT", 135.0),
+    ("LEFT", 180.0),
+    ("DOWN_LEFT", 225.0),
+    ("DOWN", 270.0),
+    ("DOWN_RIGHT", 315.0),
+];
+
+/// Return the angle to add to from to face the same direction as to, turning the shortest way. In radians, between -PI and PI.
+pub fn angle_difference(from: f64, to: f64) -> f64 {
+    let difference = (to - from).rem_euclid(2.0 * PI);
+    if difference > PI {
+        difference - 2.0 * PI
+    } else {
+        difference
+    }
+}
+
+#[test]
+fn test_angle_difference() {
+    let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+    assert!(close(angle_difference(0.0, PI / 2.0), PI / 2.0));
+    assert!(close(angle_difference(PI / 4.0, -PI / 4.0), -PI / 2.0));
+    // turn through the right rather than making almost a full turn
+    assert!(close(angle_difference(1.75 * PI, 0.25 * PI), 0.5 * PI));
+    assert!(close(angle_difference(5.0 * PI, 0.0), PI));
+}
@@ -1,8 +1,71 @@
 use mlua::{FromLua, UserData};
 
-#[derive(Debug, Clone, PartialEq, FromLua)]
+/// The emotion shown by a portrait
+#[derive(Debug, Clone, Copy, PartialEq, FromLua)]
+#[allow(clippy::upper_case_acronyms, non_camel_case_types)]
 pub enum FaceType {
     NORMAL,
+    HAPPY,
+    PAIN,
+    ANGRY,
+    WORRIED,
+    SAD,
+    CRYING,
+    SHOUTING,
+    TEARY_EYED,
+    DETERMINED,
+    JOYOUS,
+    INSPIRED,
+    SURPRISED,
+    DIZZY,
+    SIGH,
+    STUNNED,
+}
+
+impl FaceType {
+    /// Every `FaceType`, with the name used by the scripts
+    pub const ALL: [(&'static str, FaceType); 16] = [
+        ("NORMAL", Self::NORMAL),
+        ("HAPPY", Self::HAPPY),
+        ("PAIN", Self::PAIN),
+        ("ANGRY", Self::ANGRY),
+        ("WORRIED", Self::WORRIED),
+        ("SAD", Self::SAD),
+        ("CRYING", Self::CRYING),
+        ("SHOUTING", Self::SHOUTING),
+        ("TEARY_EYED", Self::TEARY_EYED),
+        ("DETERMINED", Self::DETERMINED),
+        ("JOYOUS", Self::JOYOUS),
+        ("INSPIRED", Self::INSPIRED),
+        ("SURPRISED", Self::SURPRISED),
+        ("DIZZY", Self::DIZZY),
+        ("SIGH", Self::SIGH),
+        ("STUNNED", Self::STUNNED),
+    ];
+
+    /// The slot of this emotion in an entry of explorer of sky ``FONT/kaomado.kao``.
+    /// The following slot contain the same portrait, mirrored.
+    pub fn kaomado_slot(&self) -> usize {
+        match self {
+            Self::NORMAL => 0,
+            Self::HAPPY => 2,
+            Self::PAIN => 4,
+            Self::ANGRY => 6,
+            Self::WORRIED => 8,
+            Self::SAD => 10,
+            Self::CRYING => 12,
+            Self::SHOUTING => 14,
+            Self::TEARY_EYED => 16,
+            Self::DETERMINED => 18,
+            Self::JOYOUS => 20,
+            Self::INSPIRED => 22,
+            Self::SURPRISED => 24,
+            Self::DIZZY => 26,
+            // slot 28 and 30 are unused
+            Self::SIGH => 32,
+            Self::STUNNED => 34,
+        }
+    }
 }
 
 impl UserData for FaceType {}
@@ -0,0 +1,164 @@
+use crate::gamedata::{Color, Time, Vec2_f64, Vec3_f64, TIME_EPSILON};
+use mlua::{FromLua, Lua, Value};
+use std::f64::consts::PI;
+
+/// The shape of a transition: how fast it progress over its duration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Easing {
+    /// At constant speed
+    #[default]
+    Linear,
+    /// Starting slowly, then accelerating
+    EaseIn,
+    /// Starting fast, then slowing down until it stop
+    EaseOut,
+    /// Starting and stopping slowly, following a sine
+    Sine,
+}
+
+impl Easing {
+    /// The names used by the scripts, in the ``EASE`` table
+    pub const ALL: [(&'static str, Easing); 4] = [
+        ("LINEAR", Self::Linear),
+        ("EASE_IN", Self::EaseIn),
+        ("EASE_OUT", Self::EaseOut),
+        ("SINE", Self::Sine),
+    ];
+
+    /// Return the progress of the transition, from the proportion of its duration elapsed. Both goes from 0 to 1.
+    pub fn apply(self, proportion: f64) -> f64 {
+        let proportion = proportion.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => proportion,
+            Self::EaseIn => proportion * proportion,
+            Self::EaseOut => 1.0 - (1.0 - proportion) * (1.0 - proportion),
+            Self::Sine => (1.0 - (proportion * PI).cos()) / 2.0,
+        }
+    }
+}
+
+impl FromLua for Easing {
+    fn from_lua(value: Value, lua: &Lua) -> mlua::Result<Self> {
+        let name = String::from_lua(value, lua)?;
+        Self::ALL
+            .iter()
+            .find(|(easing_name, _)| *easing_name == name)
+            .map(|(_, easing)| *easing)
+            .ok_or_else(|| mlua::Error::external(format!("unknown easing {:?}", name)))
+    }
+}
+
+/// A value that can be progressively changed into another
+pub trait Interpolate {
+    /// Return the value at progress between self (at 0) and other (at 1)
+    fn interpolate(&self, other: &Self, progress: f64) -> Self;
+}
+
+impl Interpolate for f64 {
+    fn interpolate(&self, other: &Self, progress: f64) -> Self {
+        // exact at both ends
+        self * (1.0 - progress) + other * progress
+    }
+}
+
+impl Interpolate for Vec2_f64 {
+    fn interpolate(&self, other: &Self, progress: f64) -> Self {
+        Vec2_f64::new(
+            self.x.interpolate(&other.x, progress),
+            self.y.interpolate(&other.y, progress),
+        )
+    }
+}
+
+impl Interpolate for Vec3_f64 {
+    fn interpolate(&self, other: &Self, progress: f64) -> Self {
+        Vec3_f64::new(
+            self.x.interpolate(&other.x, progress),
+            self.y.interpolate(&other.y, progress),
+            self.z.interpolate(&other.z, progress),
+        )
+    }
+}
+
+impl Interpolate for Color {
+    fn interpolate(&self, other: &Self, progress: f64) -> Self {
+        let progress = progress as f32;
+        Color {
+            r: self.r + (other.r - self.r) * progress,
+            g: self.g + (other.g - self.g) * progress,
+            b: self.b + (other.b - self.b) * progress,
+            a: self.a + (other.a - self.a) * progress,
+        }
+    }
+}
+
+/// A transition from a value to another, lasting a given time
+#[derive(Debug, Clone)]
+pub struct Tween<T> {
+    pub from: T,
+    pub to: T,
+    duration: Time,
+    elapsed: Time,
+    easing: Easing,
+}
+
+impl<T: Interpolate + Clone> Tween<T> {
+    pub fn new(from: T, to: T, duration: Time, easing: Easing) -> Self {
+        Self {
+            from,
+            to,
+            duration,
+            elapsed: Time::new(0.0),
+            easing,
+        }
+    }
+
+    pub fn time_spent(&mut self, time: Time) {
+        self.elapsed += time;
+    }
+
+    /// Return ``true`` once the whole duration elapsed. A tween with a null duration is immediately finished.
+    pub fn is_finished(&self) -> bool {
+        self.elapsed.get_time() >= self.duration.get_time() - TIME_EPSILON
+    }
+
+    /// The value at the current time. Exactly the final one once finished.
+    pub fn value(&self) -> T {
+        if self.is_finished() {
+            self.to.clone()
+        } else {
+            let proportion = self.elapsed.get_time() / self.duration.get_time();
+            self.from
+                .interpolate(&self.to, self.easing.apply(proportion))
+        }
+    }
+}
+
+#[test]
+fn test_easing() {
+    for (_, easing) in Easing::ALL {
+        assert_eq!(easing.apply(0.0), 0.0);
+        assert!((easing.apply(1.0) - 1.0).abs() < 1e-9);
+    }
+    assert_eq!(Easing::Linear.apply(0.25), 0.25);
+    assert!(Easing::EaseIn.apply(0.25) < 0.25);
+    assert!(Easing::EaseOut.apply(0.25) > 0.25);
+    assert!((Easing::Sine.apply(0.5) - 0.5).abs() < 1e-9);
+}
+
+#[test]
+fn test_tween() {
+    let mut tween = Tween::new(
+        Vec2_f64::new(0.0, 0.0),
+        Vec2_f64::new(2.0, 4.0),
+        Time::new(1.0),
+        Easing::EaseIn,
+    );
+    tween.time_spent(Time::new(0.5));
+    assert_eq!(tween.value(), Vec2_f64::new(0.5, 1.0));
+    assert!(!tween.is_finished());
+    tween.time_spent(Time::new(0.6));
+    assert!(tween.is_finished());
+    assert_eq!(tween.value(), Vec2_f64::new(2.0, 4.0));
+    assert!(Tween::new(1.0, 2.0, Time::new(0.0), Easing::Sine).is_finished());
+}
@@ -1,8 +1,44 @@
-use crate::gamedata::Time;
-use std::sync::{atomic::AtomicBool, Arc};
+use crate::gamedata::{PadButton, Time};
+use crate::{ResumeValue, WakeFlag};
+use std::sync::{Arc, Mutex};
 
 #[derive(Debug, Clone)]
 pub enum Lock {
-    Wait(Arc<AtomicBool>, Time),
-    WaitMove(Arc<AtomicBool>, String),
+    Wait(WakeFlag, Time),
+    WaitMove(WakeFlag, String),
+    /// Released once the character (by it's id) finished turning
+    WaitTurn(WakeFlag, String),
+    /// Released once the character (by it's id) landed, or reached the height it was set to
+    WaitHeight(WakeFlag, String),
+    /// Released once the camera stopped moving, zooming and rotating
+    WaitCamera(WakeFlag),
+    /// Released once the animation played once by the character (by it's id) finished. Immediately if it play a
+    /// looping one.
+    WaitMotion(WakeFlag, String),
+    /// Released when the player confirm, once the displayed message (if any) is entirely shown
+    WaitMessage(WakeFlag),
+    /// Released when the player press the button, or any button if ``None``
+    WaitButton(WakeFlag, Option<PadButton>),
+    /// Released when the player choose an option of the menu. Its number, starting at 1, is stored in the `ResumeValue`.
+    WaitChoice(WakeFlag, Arc<Mutex<ResumeValue>>),
+    /// Released when the signal of this name is raised. Immediately if it already is.
+    WaitSignal(WakeFlag, String),
+}
+
+impl Lock {
+    /// The flag woken when this lock is released
+    pub fn flag(&self) -> &WakeFlag {
+        match self {
+            Lock::Wait(flag, _)
+            | Lock::WaitMove(flag, _)
+            | Lock::WaitTurn(flag, _)
+            | Lock::WaitHeight(flag, _)
+            | Lock::WaitMotion(flag, _)
+            | Lock::WaitCamera(flag)
+            | Lock::WaitMessage(flag)
+            | Lock::WaitButton(flag, _)
+            | Lock::WaitChoice(flag, _)
+            | Lock::WaitSignal(flag, _) => flag,
+        }
+    }
 }
@@ -0,0 +1,31 @@
+/// A list of options the player choose from
+#[derive(Debug, Clone)]
+pub struct Menu {
+    pub options: Vec<String>,
+    /// The index of the option under the cursor
+    pub cursor: usize,
+}
+
+impl Menu {
+    pub fn new(options: Vec<String>) -> Self {
+        Self { options, cursor: 0 }
+    }
+
+    /// Move the cursor by offset options, looping at both ends
+    pub fn move_cursor(&mut self, offset: isize) {
+        if self.options.is_empty() {
+            return;
+        };
+        let lenght = self.options.len() as isize;
+        self.cursor = (self.cursor as isize + offset).rem_euclid(lenght) as usize;
+    }
+}
+
+#[test]
+fn test_menu_cursor() {
+    let mut menu = Menu::new(vec!["Yes".into(), "No".into(), "Maybe".into()]);
+    menu.move_cursor(-1);
+    assert_eq!(menu.cursor, 2);
+    menu.move_cursor(2);
+    assert_eq!(menu.cursor, 1);
+}
@@ -0,0 +1,206 @@
+use crate::gamedata::{Color, Time};
+
+/// The time between the apparition of two characters of a message, in second
+pub const CHARACTER_DELAY: f64 = 1.0 / 30.0;
+/// The framerate of the frame unit used by the ``[W:n]`` control code
+const CONTROL_CODE_FRAMERATE: u32 = 60;
+
+/// An element of a message, once its control codes are parsed
+#[derive(Debug, Clone, PartialEq)]
+pub enum MessageElement {
+    /// A character to display
+    Char(char),
+    /// Start a new line, from ``\n``
+    NewLine,
+    /// Change the color of the following text, from ``[CS:x]``. ``None`` come back to the default color, from ``[CR]``.
+    Color(Option<Color>),
+    /// Pause the typewriter, from ``[W:n]``, with n in frame
+    Wait(Time),
+    /// Pause the typewriter until the player confirm, from ``[K]``
+    KeyWait,
+}
+
+/// Return the color of a ``[CS:x]`` control code
+fn control_code_color(code: &str) -> Color {
+    let (r, g, b) = match code {
+        "W" => (1.0, 1.0, 1.0),
+        "R" => (1.0, 0.35, 0.35),
+        "G" => (0.4, 1.0, 0.4),
+        "B" => (0.4, 0.6, 1.0),
+        "C" => (0.4, 1.0, 1.0),
+        "P" => (1.0, 0.55, 0.85),
+        "Y" | "K" => (1.0, 0.9, 0.2),
+        other => {
+            debug!("unknown text color {:?}, displayed in yellow", other);
+            (1.0, 0.9, 0.2)
+        }
+    };
+    Color { r, g, b, a: 1.0 }
+}
+
+/// Parse the text of a message, interpreting its control codes (between square brackets).
+///
+/// Unknown control codes are ignored, rather than displayed.
+pub fn parse_message(text: &str) -> Vec<MessageElement> {
+    let mut elements = Vec::new();
+    let mut rest = text;
+    while let Some(character) = rest.chars().next() {
+        if character == '[' {
+            if let Some(end) = rest.find(']') {
+                let control_code = &rest[1..end];
+                rest = &rest[end + 1..];
+                let (name, argument) = match control_code.split_once(':') {
+                    Some((name, argument)) => (name, Some(argument)),
+                    None => (control_code, None),
+                };
+                match (name, argument) {
+                    ("CS", Some(color)) => {
+                        elements.push(MessageElement::Color(Some(control_code_color(color))))
+                    }
+                    ("CR", None) => elements.push(MessageElement::Color(None)),
+                    ("K", None) => elements.push(MessageElement::KeyWait),
+                    ("W", Some(frames)) => match frames.parse::<u64>() {
+                        Ok(frames) => elements.push(MessageElement::Wait(Time::from_frames(
+                            frames,
+                            CONTROL_CODE_FRAMERATE,
+                        ))),
+                        Err(_) => warn!("invalid wait duration in the message: {:?}", frames),
+                    },
+                    _ => debug!("ignored the control code {:?} of a message", control_code),
+                };
+                continue;
+            };
+        };
+        elements.push(match character {
+            '\n' => MessageElement::NewLine,
+            character => MessageElement::Char(character),
+        });
+        rest = &rest[character.len_utf8()..];
+    }
+    elements
+}
+
+/// A message displayed in the text box, appearing progressively
+#[derive(Debug, Clone)]
+pub struct Message {
+    /// The name of the character talking, if any
+    pub speaker: Option<String>,
+    pub elements: Vec<MessageElement>,
+    /// The number of elements already reached by the typewriter
+    shown: usize,
+    /// The time before the typewriter reach the next element. Negative when late.
+    delay: Time,
+    /// The typewriter is paused by a ``[K]``
+    key_wait: bool,
+}
+
+impl Message {
+    pub fn new(speaker: Option<String>, text: &str) -> Self {
+        Self {
+            speaker,
+            elements: parse_message(text),
+            shown: 0,
+            delay: Time::new(0.0),
+            key_wait: false,
+        }
+    }
+
+    /// The elements that are already displayed
+    pub fn shown_elements(&self) -> &[MessageElement] {
+        &self.elements[..self.shown]
+    }
+
+    /// Return ``true`` if the whole message is displayed
+    pub fn is_complete(&self) -> bool {
+        self.shown >= self.elements.len()
+    }
+
+    /// Return ``true`` if the message is paused until the player confirm
+    pub fn is_waiting_key(&self) -> bool {
+        self.key_wait
+    }
+
+    /// Reach the next element. Return ``false`` if the typewriter should stop there.
+    fn reach_next_element(&mut self) -> bool {
+        let element = &self.elements[self.shown];
+        self.shown += 1;
+        match element {
+            MessageElement::Char(_) => self.delay += Time::new(CHARACTER_DELAY),
+            MessageElement::Wait(duration) => self.delay += *duration,
+            MessageElement::KeyWait => {
+                self.key_wait = true;
+                return false;
+            }
+            MessageElement::NewLine | MessageElement::Color(_) => (),
+        };
+        true
+    }
+
+    pub fn time_spent(&mut self, time: Time) {
+        if self.key_wait {
+            return;
+        };
+        self.delay -= time;
+        while self.delay.get_time() <= 0.0 && !self.is_complete() {
+            if !self.reach_next_element() {
+                break;
+            };
+        }
+        if self.key_wait || self.is_complete() {
+            self.delay = Time::new(0.0);
+        };
+    }
+
+    /// The player confirmed. Resume after a ``[K]``, or display the text immediately up to the next one if the
+    /// typewriter is running.
+    ///
+    /// Return ``true`` if the message was already entirely displayed, and is thus read.
+    pub fn confirm(&mut self) -> bool {
+        if self.key_wait {
+            self.key_wait = false;
+            return false;
+        };
+        if self.is_complete() {
+            return true;
+        };
+        while !self.is_complete() && self.reach_next_element() {}
+        self.delay = Time::new(0.0);
+        false
+    }
+}
+
+#[test]
+fn test_parse_message() {
+    let elements = parse_message("A[CS:R]b[CR]\n[W:30][K][unknown]c[");
+    assert_eq!(
+        elements,
+        vec![
+            MessageElement::Char('A'),
+            MessageElement::Color(Some(control_code_color("R"))),
+            MessageElement::Char('b'),
+            MessageElement::Color(None),
+            MessageElement::NewLine,
+            MessageElement::Wait(Time::new(0.5)),
+            MessageElement::KeyWait,
+            MessageElement::Char('c'),
+            MessageElement::Char('['),
+        ]
+    );
+}
+
+#[test]
+fn test_message_typewriter() {
+    let mut message = Message::new(None, "ab[K]cd[W:60]e");
+    message.time_spent(Time::new(0.0));
+    assert_eq!(message.shown_elements(), &[MessageElement::Char('a')]);
+    message.time_spent(Time::new(1.0));
+    assert!(message.is_waiting_key());
+    assert_eq!(message.shown_elements().len(), 3);
+    // resume after [K], then skip the typewriter
+    assert!(!message.confirm());
+    message.time_spent(Time::new(0.0));
+    assert_eq!(message.shown_elements().len(), 4);
+    assert!(!message.confirm());
+    assert!(message.is_complete());
+    assert!(message.confirm());
+}
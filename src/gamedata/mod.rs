@@ -10,17 +10,32 @@ mod vectors;
 pub use vectors::{Vec2_f64, Vec3_f64};
 
 mod characters;
-pub use characters::Chara;
+pub use characters::{Chara, MoveKind};
+
+mod motion;
+pub use motion::{Motion, MOTIONS, MOTION_IDLE, MOTION_RUN, MOTION_WALK};
+
+mod direction;
+pub use direction::{angle_difference, DIRECTIONS};
 
 mod various;
-pub use various::Time;
+pub use various::{Time, TIME_EPSILON};
+
+mod interpolation;
+pub use interpolation::{Easing, Interpolate, Tween};
 
 mod lock;
 pub use lock::Lock;
 
+mod timers;
+pub use timers::Timers;
+
 mod color;
 pub use color::Color;
 
+mod camera;
+pub use camera::Camera;
+
 mod screen;
 pub use screen::Screen;
 
@@ -32,3 +47,15 @@ pub use facetype::FaceType;
 
 mod portrait;
 pub use portrait::Portrait;
+
+mod message;
+pub use message::{parse_message, Message, MessageElement, CHARACTER_DELAY};
+
+mod menu;
+pub use menu::Menu;
+
+mod pad;
+pub use pad::{PadButton, PadState};
+
+mod placement;
+pub use placement::{NamingMode, PlacedActor, Placement, UNDECLARED_CHARA_ID};
@@ -0,0 +1,62 @@
+/// The name of the animation played when a character is idle
+pub const MOTION_IDLE: &str = "idle";
+/// The name of the animation played when a character walk
+pub const MOTION_WALK: &str = "walk";
+/// The name of the animation played when a character run
+pub const MOTION_RUN: &str = "run";
+
+/// The motion names of the scripts, with the name of the animation they play (see `crate::render::SpeciesEntry`)
+pub const MOTIONS: [(&str, &str); 6] = [
+    ("WAIT", MOTION_IDLE),
+    ("WALK", MOTION_WALK),
+    ("RUN", MOTION_RUN),
+    ("TALK", "talk"),
+    ("NOD", "nod"),
+    ("SLEEP", "sleep"),
+];
+
+/// The animation played by a character
+#[derive(Debug, Clone, PartialEq)]
+pub struct Motion {
+    /// The name of the animation
+    pub name: String,
+    /// If ``false``, the animation is played once, then the character come back to `MOTION_IDLE`
+    pub looping: bool,
+}
+
+impl Motion {
+    /// Create a new `Motion`. name is either a motion name of the scripts (like ``WAIT``) or an animation name.
+    pub fn new(name: &str, looping: bool) -> Self {
+        let name = MOTIONS
+            .iter()
+            .find(|(motion, _)| *motion == name)
+            .map(|(_, animation)| *animation)
+            .unwrap_or(name);
+        Self {
+            name: name.to_string(),
+            looping,
+        }
+    }
+
+    pub fn idle() -> Self {
+        Self::new(MOTION_IDLE, true)
+    }
+
+    /// Return ``true`` if this is a motion played once, that isn't finished
+    pub fn is_one_shot(&self) -> bool {
+        !self.looping
+    }
+}
+
+impl Default for Motion {
+    fn default() -> Self {
+        Self::idle()
+    }
+}
+
+#[test]
+fn test_motion_name() {
+    assert_eq!(Motion::new("WAIT", true), Motion::idle());
+    assert_eq!(Motion::new("NOD", false).name, "nod");
+    assert_eq!(Motion::new("attack", false).name, "attack");
+}
@@ -0,0 +1,115 @@
+use crate::gamedata::Vec2_f64;
+use mlua::{FromLua, UserData};
+
+/// A button of the 3DS
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromLua)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum PadButton {
+    A,
+    B,
+    X,
+    Y,
+    L,
+    R,
+    START,
+    SELECT,
+    UP,
+    DOWN,
+    LEFT,
+    RIGHT,
+}
+
+impl PadButton {
+    /// Every `PadButton`, with the name used by the scripts
+    pub const ALL: [(&'static str, PadButton); 12] = [
+        ("A", Self::A),
+        ("B", Self::B),
+        ("X", Self::X),
+        ("Y", Self::Y),
+        ("L", Self::L),
+        ("R", Self::R),
+        ("START", Self::START),
+        ("SELECT", Self::SELECT),
+        ("UP", Self::UP),
+        ("DOWN", Self::DOWN),
+        ("LEFT", Self::LEFT),
+        ("RIGHT", Self::RIGHT),
+    ];
+}
+
+impl UserData for PadButton {}
+
+/// The state of the buttons and of the circle pad during a frame
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PadState {
+    /// The buttons pressed since the previous frame
+    pub pressed: Vec<PadButton>,
+    /// The buttons actually held down
+    pub held: Vec<PadButton>,
+    /// The direction of the circle pad, with a lenght of at most 1. y point to the top of the screen.
+    pub direction: Vec2_f64,
+}
+
+impl PadState {
+    /// Return ``true`` if the button was pressed since the previous frame
+    pub fn is_pressed(&self, button: PadButton) -> bool {
+        self.pressed.contains(&button)
+    }
+
+    /// Return ``true`` if the button is held down
+    pub fn is_held(&self, button: PadButton) -> bool {
+        self.held.contains(&button)
+    }
+
+    pub fn press(&mut self, button: PadButton) {
+        if !self.is_pressed(button) {
+            self.pressed.push(button);
+        };
+        if !self.is_held(button) {
+            self.held.push(button);
+        };
+    }
+
+    pub fn release(&mut self, button: PadButton) {
+        self.held.retain(|held| *held != button);
+    }
+
+    /// Forget the pressed buttons, keeping the held ones, for the next frame
+    pub fn new_frame(&mut self) {
+        self.pressed.clear();
+    }
+
+    /// Set the direction from the held buttons of the directional pad
+    pub fn set_direction_from_dpad(&mut self) {
+        let axis = |positive, negative| {
+            self.is_held(positive) as i32 as f64 - self.is_held(negative) as i32 as f64
+        };
+        let (x, y) = (
+            axis(PadButton::RIGHT, PadButton::LEFT),
+            axis(PadButton::UP, PadButton::DOWN),
+        );
+        let lenght = (x * x + y * y).sqrt();
+        self.direction = if lenght > 0.0 {
+            Vec2_f64::new(x / lenght, y / lenght)
+        } else {
+            Vec2_f64::default()
+        };
+    }
+}
+
+#[test]
+fn test_pad_state() {
+    let mut pad = PadState::default();
+    pad.press(PadButton::UP);
+    pad.press(PadButton::RIGHT);
+    pad.set_direction_from_dpad();
+    assert!(pad.is_pressed(PadButton::UP));
+    assert!((pad.direction.x - 0.5f64.sqrt()).abs() < 1e-9);
+    assert_eq!(pad.direction.x, pad.direction.y);
+    pad.new_frame();
+    pad.release(PadButton::RIGHT);
+    pad.set_direction_from_dpad();
+    assert!(!pad.is_pressed(PadButton::UP));
+    assert!(pad.is_held(PadButton::UP));
+    assert_eq!(pad.direction, Vec2_f64::new(0.0, 1.0));
+}
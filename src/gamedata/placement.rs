@@ -0,0 +1,79 @@
+use crate::gamedata::Vec3_f64;
+use serde::Deserialize;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+/// How the name given to a character (like in ``CH("HERO")``) are resolved
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum NamingMode {
+    /// Gate to infinity: any name can be used, and each one refer to a different character
+    #[default]
+    Gti,
+    /// Super mystery dungeon: only the names declared by the placement (PLB) are distinct characters.
+    /// Every other name refer to the same character, `UNDECLARED_CHARA_ID`.
+    Psmd,
+}
+
+/// The id every undeclared name refer to, in `NamingMode::Psmd`
+pub const UNDECLARED_CHARA_ID: &str = "_UNDECLARED";
+
+/// A character slot declared by a placement file
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PlacedActor {
+    /// The name used by the script to refer to this character
+    pub name: String,
+    /// The actor (specie) to load at the start of the scene. If ``None``, the slot is empty until ``CHARA:DynamicLoad`` is called.
+    #[serde(default, alias = "specie")]
+    pub actor: Option<String>,
+    /// The initial position
+    #[serde(default)]
+    pub position: Vec3_f64,
+    /// The initial direction, in degree
+    #[serde(default, alias = "angle")]
+    pub direction: f64,
+}
+
+/// The content of a placement (PLB) file, that declare the characters of a level.
+///
+/// It is read from the json produced by eddyk28's PLB tool, like ``{"actors": [{"name": "HERO", "actor": "KIBAGO", "position": {"x": 1, "y": 2, "z": 0}, "direction": 90}]}``.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+pub struct Placement {
+    #[serde(default, alias = "characters")]
+    pub actors: Vec<PlacedActor>,
+}
+
+impl Placement {
+    pub fn from_json<R: Read>(reader: R) -> serde_json::Result<Self> {
+        serde_json::from_reader(reader)
+    }
+
+    /// Read a json placement file
+    pub fn open(path: &Path) -> serde_json::Result<Self> {
+        let file = File::open(path).map_err(serde_json::Error::io)?;
+        Self::from_json(BufReader::new(file))
+    }
+
+    /// Return the slot declared with this name, if any
+    pub fn get_actor(&self, name: &str) -> Option<&PlacedActor> {
+        self.actors.iter().find(|actor| actor.name == name)
+    }
+}
+
+#[test]
+fn test_placement_json() {
+    let placement = Placement::from_json(
+        "{\"actors\": [
+            {\"name\": \"HERO\", \"actor\": \"KIBAGO\", \"position\": {\"x\": 1, \"y\": 2}, \"direction\": 90},
+            {\"name\": \"PARTNER\"}
+        ]}"
+        .as_bytes(),
+    )
+    .unwrap();
+    let hero = placement.get_actor("HERO").unwrap();
+    assert_eq!(hero.actor.as_deref(), Some("KIBAGO"));
+    assert_eq!(hero.position, Vec3_f64::new(1.0, 2.0, 0.0));
+    assert_eq!(hero.direction, 90.0);
+    assert_eq!(placement.get_actor("PARTNER").unwrap().actor, None);
+    assert!(placement.get_actor("NPC").is_none());
+}
@@ -1,118 +1,455 @@
 use drain_filter_polyfill::VecExt;
 
-use crate::gamedata::{Chara, Lock, Portrait, Screen, Update};
-use std::collections::HashMap;
-use std::mem::swap;
-use std::sync::atomic::Ordering::Relaxed;
+use crate::gamedata::{
+    Camera, Chara, Lock, Menu, Message, Motion, MoveKind, NamingMode, PadButton, PadState,
+    Placement, Portrait, Screen, Timers, Update, UNDECLARED_CHARA_ID,
+};
+use crate::{ResumeValue, WakeFlag};
+use std::collections::{BTreeMap, BTreeSet};
+use std::mem::{swap, take};
 
 #[derive(Debug)]
 pub struct Scene {
-    pub charas: HashMap<String, Chara>,
+    /// The characters, ordered by id so they are always updated in the same order
+    pub charas: BTreeMap<String, Chara>,
     updates: Vec<Update>,
+    /// The locks released by the input of the player
     locks: Vec<Lock>,
+    /// The locks released after some time (`Lock::Wait`)
+    timers: Timers,
+    /// The locks released once a character stop moving (`Lock::WaitMove`), by character id
+    move_locks: BTreeMap<String, Vec<WakeFlag>>,
+    /// The locks released once a character stop turning (`Lock::WaitTurn`), by character id
+    turn_locks: BTreeMap<String, Vec<WakeFlag>>,
+    /// The locks released once the height of a character stop changing (`Lock::WaitHeight`), by character id
+    height_locks: BTreeMap<String, Vec<WakeFlag>>,
+    /// The locks released once a character finish the motion it play once (`Lock::WaitMotion`), by character id
+    motion_locks: BTreeMap<String, Vec<WakeFlag>>,
+    /// The locks released once the camera stop moving (`Lock::WaitCamera`)
+    camera_locks: Vec<WakeFlag>,
+    /// The names of the raised signals
+    signals: BTreeSet<String>,
+    pub camera: Camera,
     pub screens: Vec<Screen>, //Screen 0: upper, Screen 1: down
     pub portrait: Option<Portrait>,
+    pub message: Option<Message>,
+    pub menu: Option<Menu>,
+    /// The buttons inputed by the player during this frame
+    pub pad: PadState,
+    naming_mode: NamingMode,
+    placement: Placement,
 }
 
 impl Default for Scene {
     fn default() -> Self {
         Self {
-            charas: HashMap::new(),
+            charas: BTreeMap::new(),
             updates: Vec::new(),
             locks: Vec::new(),
+            timers: Timers::default(),
+            move_locks: BTreeMap::new(),
+            turn_locks: BTreeMap::new(),
+            height_locks: BTreeMap::new(),
+            motion_locks: BTreeMap::new(),
+            camera_locks: Vec::new(),
+            signals: BTreeSet::new(),
+            camera: Camera::default(),
             screens: vec![Screen::new(), Screen::new()],
             portrait: None,
+            message: None,
+            menu: None,
+            pad: PadState::default(),
+            naming_mode: NamingMode::default(),
+            placement: Placement::default(),
         }
     }
 }
 
 impl Scene {
+    pub fn set_naming_mode(&mut self, naming_mode: NamingMode) {
+        self.naming_mode = naming_mode;
+    }
+
+    /// Declare the character slots of the level. The characters with an actor are loaded immediately, the other
+    /// will be placed at their initial position when loaded by the script.
+    pub fn load_placement(&mut self, placement: Placement) {
+        self.placement = placement;
+        for placed in self.placement.actors.clone() {
+            if let Some(actor) = placed.actor {
+                self.update(Update::AddChara(placed.name, actor));
+            };
+        }
+    }
+
+    /// Return the id of the character a name refer to.
+    ///
+    /// There are limited named slots for characters in PSMD, so only the names declared in the placement can be used.
+    /// Nonexistent names all refer to the same character.
+    /// For example, the following will create a new Axew character, accessible by CH("Orus").
+    /// CHARA:DynamicLoad("Orus", "KIBAGO") --(name, actor)
+    /// However, any nonexistent name with refer to this character, even CH("asdfg")
+    /// As a result, the following will conflict with the already existing character and produce an error.
+    /// CHARA:DynamicLoad("Laurenna", "TSUTAAJA")
+    /// Surprisingly, this works as intended in Gates (go figure).
+    pub fn resolve_chara_id(&self, id: &str) -> String {
+        match self.naming_mode {
+            NamingMode::Psmd if self.placement.get_actor(id).is_none() => {
+                UNDECLARED_CHARA_ID.to_string()
+            }
+            _ => id.to_string(),
+        }
+    }
+
+    /// Replace the character names in the update by the id of the character they refer to
+    fn resolve_update(&self, update: Update) -> Update {
+        match update {
+            Update::AddChara(id, actor) => Update::AddChara(self.resolve_chara_id(&id), actor),
+            Update::DelChara(id) => Update::DelChara(self.resolve_chara_id(&id)),
+            Update::SetPosition(id, position) => {
+                Update::SetPosition(self.resolve_chara_id(&id), position)
+            }
+            Update::WalkTo(id, position, speed) => {
+                Update::WalkTo(self.resolve_chara_id(&id), position, speed)
+            }
+            Update::MoveAlong(id, path, speed, kind, easing) => {
+                Update::MoveAlong(self.resolve_chara_id(&id), path, speed, kind, easing)
+            }
+            Update::SetAngle(id, angle) => Update::SetAngle(self.resolve_chara_id(&id), angle),
+            Update::SetMotion(id, motion) => Update::SetMotion(self.resolve_chara_id(&id), motion),
+            Update::TurnTo(id, angle, duration) => {
+                Update::TurnTo(self.resolve_chara_id(&id), angle, duration)
+            }
+            Update::Jump(id, height, duration) => {
+                Update::Jump(self.resolve_chara_id(&id), height, duration)
+            }
+            Update::SetHeight(id, height, duration, easing) => {
+                Update::SetHeight(self.resolve_chara_id(&id), height, duration, easing)
+            }
+            Update::FollowChara(Some(id)) => Update::FollowChara(Some(self.resolve_chara_id(&id))),
+            Update::SetPortrait(mut portrait) => {
+                portrait.actor = self.resolve_chara_id(&portrait.actor);
+                Update::SetPortrait(portrait)
+            }
+            Update::AddLock(Lock::WaitMove(flag, id)) => {
+                Update::AddLock(Lock::WaitMove(flag, self.resolve_chara_id(&id)))
+            }
+            Update::AddLock(Lock::WaitTurn(flag, id)) => {
+                Update::AddLock(Lock::WaitTurn(flag, self.resolve_chara_id(&id)))
+            }
+            Update::AddLock(Lock::WaitHeight(flag, id)) => {
+                Update::AddLock(Lock::WaitHeight(flag, self.resolve_chara_id(&id)))
+            }
+            Update::AddLock(Lock::WaitMotion(flag, id)) => {
+                Update::AddLock(Lock::WaitMotion(flag, self.resolve_chara_id(&id)))
+            }
+            update => update,
+        }
+    }
+
     pub fn update(&mut self, update: Update) {
+        let update = self.resolve_update(update);
         match &update {
             Update::AddChara(id, actor) => {
-                //TODO: There are ligmited named slots for characters in PSMD, so only existing (as determined by a plb?) character names can be used.
-                //Nonexistent names all refer to the same character.
-                //For example, the following will create a new Axew character, accessible by CH("Orus").
-                //CHARA:DynamicLoad("Orus", "KIBAGO") --(name, actor)
-                //However, any nonexistent name with refer to this character, even CH("asdfg")
-                //As a result, the following will conflict with the already existing character and produce an error.
-                //CHARA:DynamicLoad("Laurenna", "TSUTAAJA")
-                //Surprisingly, this works as intended in Gates (go figure).
-                let chara = Chara::new(actor.clone());
+                if id == UNDECLARED_CHARA_ID && self.charas.contains_key(id) {
+                    error!("the character {:?} was loaded, but all undeclared names refer to the same already loaded character", actor);
+                };
+                let mut chara = Chara::new(actor.clone());
+                if let Some(placed) = self.placement.get_actor(id) {
+                    chara.position = placed.position;
+                    chara.angle = placed.direction.to_radians();
+                };
                 self.charas.insert(id.clone(), chara);
             }
             Update::DelChara(id) => {
                 self.charas.remove(id);
+                // the tasks waiting for it would never be released otherwise
+                for locks in [
+                    &mut self.move_locks,
+                    &mut self.turn_locks,
+                    &mut self.height_locks,
+                    &mut self.motion_locks,
+                ] {
+                    wake_all(locks.remove(id).as_mut());
+                }
             }
             Update::SetPosition(id, position) => {
-                self.charas
-                    .get_mut(id)
-                    .unwrap()
-                    .set_position(position.clone());
+                let chara = self.charas.get_mut(id).unwrap();
+                let was_walking = chara.moving_kind().map(MoveKind::is_animated);
+                chara.set_position(*position);
+                if was_walking == Some(true) {
+                    self.updates.push(Update::StartIDLE(id.clone()));
+                };
             }
             Update::WalkTo(id, position, speed) => {
+                let chara = self.charas.get_mut(id).unwrap();
+                chara.walk_to(*position, *speed);
+                chara.motion = Motion::idle();
+                wake_all(self.motion_locks.get_mut(id));
+            }
+            Update::MoveAlong(id, path, speed, kind, easing) => {
+                let chara = self.charas.get_mut(id).unwrap();
+                chara.move_along(path.clone(), *speed, *kind, *easing);
+                if kind.is_animated() {
+                    chara.motion = Motion::idle();
+                    wake_all(self.motion_locks.get_mut(id));
+                };
+            }
+            Update::SetMotion(id, motion) => {
+                self.charas.get_mut(id).unwrap().motion = motion.clone();
+                // the previous motion was interrupted
+                wake_all(self.motion_locks.get_mut(id));
+            }
+            Update::MotionFinished(id) => {
+                if let Some(chara) = self.charas.get_mut(id) {
+                    chara.motion = Motion::idle();
+                };
+                wake_all(self.motion_locks.get_mut(id));
+            }
+            Update::SetAngle(id, angle) => {
+                self.charas.get_mut(id).unwrap().set_angle(*angle);
+            }
+            Update::TurnTo(id, angle, duration) => {
+                self.charas.get_mut(id).unwrap().turn_to(*angle, *duration);
+            }
+            Update::Jump(id, height, duration) => {
+                self.charas.get_mut(id).unwrap().jump(*height, *duration);
+            }
+            Update::SetHeight(id, height, duration, easing) => {
                 self.charas
                     .get_mut(id)
                     .unwrap()
-                    .walk_to(position.clone(), speed.clone());
+                    .set_height(*height, *duration, *easing);
+            }
+            Update::AddLock(Lock::Wait(flag, duration)) => self.timers.add(*duration, flag.clone()),
+            Update::AddLock(Lock::WaitMove(flag, charid)) => self
+                .move_locks
+                .entry(charid.clone())
+                .or_default()
+                .push(flag.clone()),
+            Update::AddLock(Lock::WaitTurn(flag, charid)) => self
+                .turn_locks
+                .entry(charid.clone())
+                .or_default()
+                .push(flag.clone()),
+            Update::AddLock(Lock::WaitHeight(flag, charid)) => self
+                .height_locks
+                .entry(charid.clone())
+                .or_default()
+                .push(flag.clone()),
+            Update::AddLock(Lock::WaitMotion(flag, charid)) => match self.charas.get(charid) {
+                Some(chara) if chara.motion.is_one_shot() => self
+                    .motion_locks
+                    .entry(charid.clone())
+                    .or_default()
+                    .push(flag.clone()),
+                _ => flag.wake(),
+            },
+            Update::AddLock(Lock::WaitCamera(flag)) => self.camera_locks.push(flag.clone()),
+            Update::AddLock(Lock::WaitSignal(flag, name)) if self.signals.contains(name) => {
+                flag.wake()
             }
             Update::AddLock(lock) => self.locks.push(lock.clone()),
             Update::TimeSpent(time) => {
-                self.locks.drain_filter(|lock| match lock {
-                    Lock::Wait(lock, remaining_time) => {
-                        *remaining_time -= *time;
-                        if remaining_time.get_time() < 0.0 {
-                            lock.store(true, Relaxed);
-                            true
-                        } else {
-                            false
-                        }
-                    }
-                    _ => false,
-                });
+                self.drop_cancelled_locks();
+                self.timers.time_spent(*time);
                 for screen in &mut self.screens {
                     screen.time_spent(*time);
                 }
+                if let Some(message) = &mut self.message {
+                    message.time_spent(*time);
+                };
                 for (charid, chara) in self.charas.iter_mut() {
+                    let was_walking = chara.moving_kind().map(MoveKind::is_animated);
                     if chara.time_spent(*time) {
-                        self.updates.push(Update::StartIDLE(charid.clone()));
-                        self.locks.drain_filter(|lock| match lock {
-                            Lock::WaitMove(lock, lock_charid) => {
-                                if charid == lock_charid {
-                                    lock.store(true, Relaxed);
-                                    true
-                                } else {
-                                    false
-                                }
-                            }
-                            _ => false,
-                        });
-                        ()
+                        if was_walking == Some(true) {
+                            self.updates.push(Update::StartIDLE(charid.clone()));
+                        };
+                        wake_all(self.move_locks.get_mut(charid));
+                    };
+                    if !chara.is_turning() {
+                        wake_all(self.turn_locks.get_mut(charid));
+                    };
+                    if !chara.is_moving_vertically() {
+                        wake_all(self.height_locks.get_mut(charid));
                     };
                 }
+                self.camera.time_spent(*time);
+                self.follow_chara();
+                if !self.camera.is_moving() {
+                    wake_all(Some(&mut self.camera_locks));
+                };
+            }
+            Update::MoveCamera(position, duration, easing) => {
+                self.camera.move_focus(*position, *duration, *easing)
+            }
+            Update::FollowChara(charid) => {
+                self.camera.follow(charid.clone());
+                self.follow_chara();
+            }
+            Update::ZoomCamera(zoom, duration, easing) => {
+                self.camera.zoom_to(*zoom, *duration, *easing)
+            }
+            Update::RotateCamera(angle, duration, easing) => {
+                self.camera.rotate_to(*angle, *duration, *easing)
             }
             Update::SetScreenColor(screen_id, color) => {
                 self.screens[*screen_id as usize].set_color_immediate(color.clone())
             }
-            Update::TransitionScreenColor(screen_id, duration, color) => self.screens
+            Update::TransitionScreenColor(screen_id, duration, color, easing) => self.screens
                 [*screen_id as usize]
-                .set_color_transition(duration.clone(), color.clone()),
+                .set_color_transition(*duration, color.clone(), *easing),
+            Update::TransitionScreenTint(screen_id, duration, color, easing) => self.screens
+                [*screen_id as usize]
+                .set_tint_transition(*duration, color.clone(), *easing),
+            Update::FlashScreen(screen_id, duration, color) => {
+                self.screens[*screen_id as usize].flash(*duration, color.clone())
+            }
+            Update::QuakeScreen(screen_id, amplitude, duration) => {
+                self.screens[*screen_id as usize].quake(*amplitude, *duration)
+            }
             Update::StartIDLE(_) => (),
             Update::SetPortrait(portrait) => self.portrait = Some(portrait.clone()),
             Update::RemovePortrait => self.portrait = None,
+            Update::ShowMessage(message) => self.message = Some(message.clone()),
+            Update::CloseMessage => self.message = None,
+            Update::ShowMenu(menu) => self.menu = Some(menu.clone()),
+            Update::RaiseSignal(name) => {
+                self.signals.insert(name.clone());
+                self.locks.drain_filter(|lock| match lock {
+                    Lock::WaitSignal(lock, lock_name) if lock_name == name => {
+                        lock.wake();
+                        true
+                    }
+                    _ => false,
+                });
+            }
+            Update::ClearSignal(name) => {
+                self.signals.remove(name);
+            }
+            Update::Pad(pad) => {
+                self.drop_cancelled_locks();
+                self.pad = pad.clone();
+                let mut choice = None;
+                let mut read = false;
+                if let Some(menu) = &mut self.menu {
+                    if pad.is_pressed(PadButton::UP) {
+                        menu.move_cursor(-1);
+                    };
+                    if pad.is_pressed(PadButton::DOWN) {
+                        menu.move_cursor(1);
+                    };
+                    if pad.is_pressed(PadButton::A) {
+                        choice = Some(menu.cursor as i64 + 1);
+                        self.menu = None;
+                    };
+                } else if pad.is_pressed(PadButton::A) {
+                    read = match &mut self.message {
+                        Some(message) => message.confirm(),
+                        None => true,
+                    };
+                };
+                self.locks.drain_filter(|lock| match lock {
+                    Lock::WaitChoice(lock, value) => match choice {
+                        Some(choice) => {
+                            *value.lock().unwrap() = ResumeValue::Integer(choice);
+                            lock.wake();
+                            true
+                        }
+                        None => false,
+                    },
+                    Lock::WaitMessage(lock) if read => {
+                        lock.wake();
+                        true
+                    }
+                    Lock::WaitButton(lock, button) => {
+                        let released = match button {
+                            Some(button) => pad.is_pressed(*button),
+                            None => !pad.pressed.is_empty(),
+                        };
+                        if released {
+                            lock.wake();
+                        };
+                        released
+                    }
+                    _ => false,
+                });
+            }
         };
         if log_enabled!(log::Level::Debug) {
             match update {
-                Update::TimeSpent(_) => (),
+                Update::TimeSpent(_) | Update::Pad(_) => (),
                 _ => {
                     debug!("new update: {:?}", update);
-                    trace!("list of locks: {:?}", self.locks);
+                    trace!(
+                        "list of locks: {:?}, {} timers, move locks: {:?}, turn locks: {:?}, height locks: {:?}, motion locks: {:?}",
+                        self.locks,
+                        self.timers.len(),
+                        self.move_locks,
+                        self.turn_locks,
+                        self.height_locks,
+                        self.motion_locks
+                    );
                 }
             };
         };
         self.updates.push(update);
     }
 
+    /// Forget the locks nobody wait for anymore (see `WakeFlag::cancel`), closing the menu of a cancelled choice
+    fn drop_cancelled_locks(&mut self) {
+        let mut close_menu = false;
+        self.locks.drain_filter(|lock| {
+            let cancelled = lock.flag().is_cancelled();
+            if cancelled && matches!(lock, Lock::WaitChoice(_, _)) {
+                close_menu = true;
+            };
+            cancelled
+        });
+        if close_menu {
+            self.menu = None;
+        };
+    }
+
+    /// Put the focus of the camera on the character it follow, if any
+    fn follow_chara(&mut self) {
+        if let Some(chara) = self
+            .camera
+            .follow
+            .as_ref()
+            .and_then(|charid| self.charas.get(charid))
+        {
+            self.camera.focus = chara.position.to_vec2();
+        };
+    }
+
+    /// Return a button the script is waiting for, if any. To confirm a message, the button is only returned once
+    /// the message can't advance further without it.
+    pub fn expected_button(&self) -> Option<PadButton> {
+        let message_read = self
+            .message
+            .as_ref()
+            .map(|message| message.is_complete() || message.is_waiting_key())
+            .unwrap_or(true);
+        self.locks
+            .iter()
+            .filter(|lock| !lock.flag().is_cancelled())
+            .find_map(|lock| match lock {
+                Lock::WaitMessage(_) if message_read => Some(PadButton::A),
+                Lock::WaitChoice(_, _) => Some(PadButton::A),
+                Lock::WaitButton(_, button) => Some(button.unwrap_or(PadButton::A)),
+                _ => None,
+            })
+    }
+
+    /// Return the id of the characters playing a motion once, that isn't finished
+    pub fn playing_one_shot_motions(&self) -> Vec<String> {
+        self.charas
+            .iter()
+            .filter(|(_, chara)| chara.motion.is_one_shot())
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
+
     pub fn get_and_clear_updates(&mut self) -> Vec<Update> {
         let mut replace = Vec::new();
         swap(&mut self.updates, &mut replace);
@@ -120,6 +457,15 @@ impl Scene {
     }
 }
 
+/// Wake every flag of the list, if any, emptying it
+fn wake_all(flags: Option<&mut Vec<WakeFlag>>) {
+    if let Some(flags) = flags {
+        for flag in take(flags) {
+            flag.wake();
+        }
+    };
+}
+
 #[test]
 fn test_scene_get_updates() {
     let mut scene = Scene::default();
@@ -128,3 +474,124 @@ fn test_scene_get_updates() {
     assert!(!scene.get_and_clear_updates().is_empty());
     assert!(scene.get_and_clear_updates().is_empty());
 }
+
+#[test]
+fn test_scene_placement() {
+    use crate::gamedata::{PlacedActor, Vec3_f64};
+    let placement = Placement {
+        actors: vec![
+            PlacedActor {
+                name: "HERO".into(),
+                actor: Some("KIBAGO".into()),
+                position: Vec3_f64::new(1.0, 2.0, 0.0),
+                direction: 180.0,
+            },
+            PlacedActor {
+                name: "PARTNER".into(),
+                actor: None,
+                position: Vec3_f64::new(3.0, 0.0, 0.0),
+                direction: 0.0,
+            },
+        ],
+    };
+
+    for naming_mode in [NamingMode::Gti, NamingMode::Psmd] {
+        let mut scene = Scene::default();
+        scene.set_naming_mode(naming_mode);
+        scene.load_placement(placement.clone());
+        let hero = &scene.charas["HERO"];
+        assert_eq!(hero.position, Vec3_f64::new(1.0, 2.0, 0.0));
+        assert_eq!(hero.angle, std::f64::consts::PI);
+        assert!(!scene.charas.contains_key("PARTNER"));
+
+        scene.update(Update::AddChara("PARTNER".into(), "TSUTAAJA".into()));
+        assert_eq!(
+            scene.charas["PARTNER"].position,
+            Vec3_f64::new(3.0, 0.0, 0.0)
+        );
+
+        scene.update(Update::AddChara("Orus".into(), "KIBAGO".into()));
+        match naming_mode {
+            NamingMode::Gti => assert!(scene.charas.contains_key("Orus")),
+            NamingMode::Psmd => {
+                assert!(!scene.charas.contains_key("Orus"));
+                scene.update(Update::SetPosition(
+                    "asdfg".into(),
+                    Vec3_f64::new(5.0, 0.0, 0.0),
+                ));
+                assert_eq!(
+                    scene.charas[UNDECLARED_CHARA_ID].position,
+                    Vec3_f64::new(5.0, 0.0, 0.0)
+                );
+            }
+        };
+    }
+}
+
+#[test]
+fn test_scene_message_confirmation() {
+    use crate::gamedata::Time;
+    let mut scene = Scene::default();
+    let flag = WakeFlag::new();
+    scene.update(Update::ShowMessage(Message::new(None, "hi")));
+    scene.update(Update::AddLock(Lock::WaitMessage(flag.clone())));
+    assert_eq!(scene.expected_button(), None);
+    let mut confirm = PadState::default();
+    confirm.press(PadButton::A);
+    // the first confirmation only skip the typewriter
+    scene.update(Update::Pad(confirm.clone()));
+    assert_eq!(scene.expected_button(), Some(PadButton::A));
+    assert!(!flag.is_woken());
+    scene.update(Update::TimeSpent(Time::new(1.0)));
+    scene.update(Update::Pad(confirm));
+    assert!(flag.is_woken());
+    assert_eq!(scene.expected_button(), None);
+    assert!(scene.message.is_some());
+}
+
+#[test]
+fn test_scene_wait_button() {
+    let mut scene = Scene::default();
+    let wait_b = WakeFlag::new();
+    let wait_any = WakeFlag::new();
+    scene.update(Update::AddLock(Lock::WaitButton(
+        wait_b.clone(),
+        Some(PadButton::B),
+    )));
+    scene.update(Update::AddLock(Lock::WaitButton(wait_any.clone(), None)));
+    let mut pad = PadState::default();
+    pad.press(PadButton::X);
+    scene.update(Update::Pad(pad));
+    assert!(!wait_b.is_woken());
+    assert!(wait_any.is_woken());
+    assert_eq!(scene.expected_button(), Some(PadButton::B));
+    let mut pad = PadState::default();
+    pad.press(PadButton::B);
+    scene.update(Update::Pad(pad));
+    assert!(wait_b.is_woken());
+}
+
+#[test]
+fn test_scene_signal() {
+    let mut scene = Scene::default();
+    let waiting = WakeFlag::new();
+    scene.update(Update::AddLock(Lock::WaitSignal(
+        waiting.clone(),
+        "a".into(),
+    )));
+    scene.update(Update::RaiseSignal("b".into()));
+    assert!(!waiting.is_woken());
+    scene.update(Update::RaiseSignal("a".into()));
+    assert!(waiting.is_woken());
+    // a raised signal release immediately, until it is cleared
+    let late = WakeFlag::new();
+    scene.update(Update::AddLock(Lock::WaitSignal(late.clone(), "a".into())));
+    assert!(late.is_woken());
+    scene.update(Update::ClearSignal("a".into()));
+    let cleared = WakeFlag::new();
+    scene.update(Update::AddLock(Lock::WaitSignal(
+        cleared.clone(),
+        "a".into(),
+    )));
+    assert!(!cleared.is_woken());
+}
@@ -1,18 +1,36 @@
-use crate::gamedata::{Color, Time};
+use crate::gamedata::{Color, Easing, Time, Tween, TIME_EPSILON};
+use std::f64::consts::PI;
 
+/// How many times per second a quake shake the screen
+const QUAKE_FREQUENCY: f64 = 15.0;
+
+/// A color covering the screen, that fade out
+#[derive(Debug)]
+struct Flash {
+    color: Color,
+    fading: Tween<f64>,
+}
+
+/// A shaking of the screen, that calm down
 #[derive(Debug)]
-struct ColorTransition {
-    elapsed_time: Time,
+struct Quake {
+    /// The largest offset, in pixel of the 3DS screen
+    amplitude: f64,
     duration: Time,
-    initial_color: Color,
-    final_color: Color,
+    elapsed: Time,
 }
 
 #[derive(Debug)]
 pub struct Screen {
     need_update: bool,
+    /// The color covering the screen, set by the fades
     pub actual_color: Color,
-    ongoing_transition: Option<ColorTransition>,
+    ongoing_transition: Option<Tween<Color>>,
+    /// The color covering the scene, below the fades, until changed
+    pub tint: Color,
+    tint_transition: Option<Tween<Color>>,
+    flash: Option<Flash>,
+    quake: Option<Quake>,
 }
 
 impl Screen {
@@ -22,23 +40,81 @@ impl Screen {
             need_update: false,
             actual_color: Color::transparent(),
             ongoing_transition: None,
+            tint: Color::transparent(),
+            tint_transition: None,
+            flash: None,
+            quake: None,
         }
     }
 
     pub fn set_color_immediate(&mut self, dest_color: Color) {
+        self.ongoing_transition = None;
         self.actual_color = dest_color
     }
 
-    pub fn set_color_transition(&mut self, duration: Time, dest_color: Color) {
+    /// Fade from the actual color to this one. A fade in progress is replaced, the new one starting from where it was.
+    pub fn set_color_transition(&mut self, duration: Time, dest_color: Color, easing: Easing) {
         self.need_update = true;
-        self.ongoing_transition = Some(ColorTransition {
-            elapsed_time: Time::new(0.0),
+        self.ongoing_transition = Some(Tween::new(
+            self.actual_color.clone(),
+            dest_color,
             duration,
-            initial_color: self.actual_color.clone(),
-            final_color: dest_color,
+            easing,
+        ))
+    }
+
+    /// Change the tint from the actual one to this one. Like the fades, a change in progress is replaced.
+    pub fn set_tint_transition(&mut self, duration: Time, dest_color: Color, easing: Easing) {
+        self.need_update = true;
+        self.tint_transition = Some(Tween::new(self.tint.clone(), dest_color, duration, easing))
+    }
+
+    /// Cover the screen with the color, that fade out in duration. Replace the flash in progress, if any.
+    pub fn flash(&mut self, duration: Time, color: Color) {
+        self.need_update = true;
+        self.flash = Some(Flash {
+            color,
+            fading: Tween::new(1.0, 0.0, duration, Easing::Linear),
         })
     }
 
+    /// Shake the screen, up to amplitude pixel of the 3DS screen, calming down until the end of duration.
+    /// Replace the quake in progress, if any. With a null duration, it just stop the quake in progress.
+    pub fn quake(&mut self, amplitude: f64, duration: Time) {
+        if duration.get_time() <= 0.0 {
+            self.quake = None;
+            return;
+        };
+        self.need_update = true;
+        self.quake = Some(Quake {
+            amplitude,
+            duration,
+            elapsed: Time::new(0.0),
+        })
+    }
+
+    /// The color of the flash in progress, if any
+    pub fn flash_color(&self) -> Option<Color> {
+        self.flash.as_ref().map(|flash| {
+            flash
+                .color
+                .with_alpha(flash.color.a * flash.fading.value() as f32)
+        })
+    }
+
+    /// The offset of the scene caused by the quake in progress, in pixel of the 3DS screen
+    pub fn quake_offset(&self) -> (f64, f64) {
+        match &self.quake {
+            Some(quake) => {
+                let elapsed = quake.elapsed.get_time();
+                let strength = quake.amplitude * (1.0 - elapsed / quake.duration.get_time());
+                let phase = elapsed * QUAKE_FREQUENCY * 2.0 * PI;
+                (strength * phase.sin(), strength * (phase * 1.5).sin())
+            }
+            None => (0.0, 0.0),
+        }
+    }
+
     pub fn time_spent(&mut self, time: Time) {
         if !self.need_update {
             return;
@@ -46,24 +122,39 @@ impl Screen {
         let mut still_need_update = false;
 
         if let Some(transition) = &mut self.ongoing_transition {
-            transition.elapsed_time += time;
-            let elapsed_second = transition.elapsed_time.get_time();
-            let duration_second = transition.duration.get_time();
-            if elapsed_second >= duration_second {
-                self.actual_color = transition.final_color.clone();
+            transition.time_spent(time);
+            self.actual_color = transition.value();
+            if transition.is_finished() {
                 self.ongoing_transition = None;
             } else {
-                let proportion = (elapsed_second / duration_second) as f32;
-                self.actual_color = Color {
-                    r: transition.initial_color.r
-                        + (transition.final_color.r - transition.initial_color.r) * proportion,
-                    g: transition.initial_color.g
-                        + (transition.final_color.g - transition.initial_color.g) * proportion,
-                    b: transition.initial_color.b
-                        + (transition.final_color.b - transition.initial_color.b) * proportion,
-                    a: transition.initial_color.a
-                        + (transition.final_color.a - transition.initial_color.a) * proportion,
-                };
+                still_need_update = true;
+            };
+        };
+
+        if let Some(transition) = &mut self.tint_transition {
+            transition.time_spent(time);
+            self.tint = transition.value();
+            if transition.is_finished() {
+                self.tint_transition = None;
+            } else {
+                still_need_update = true;
+            };
+        };
+
+        if let Some(flash) = &mut self.flash {
+            flash.fading.time_spent(time);
+            if flash.fading.is_finished() {
+                self.flash = None;
+            } else {
+                still_need_update = true;
+            };
+        };
+
+        if let Some(quake) = &mut self.quake {
+            quake.elapsed += time;
+            if quake.elapsed.get_time() >= quake.duration.get_time() - TIME_EPSILON {
+                self.quake = None;
+            } else {
                 still_need_update = true;
             };
         };
@@ -71,3 +162,30 @@ impl Screen {
         self.need_update = still_need_update;
     }
 }
+
+#[test]
+fn test_screen_effects() {
+    let mut screen = Screen::new();
+    screen.set_color_transition(Time::new(1.0), Color::black(), Easing::Linear);
+    screen.time_spent(Time::new(0.5));
+    // a new fade start from where the previous one was
+    screen.set_color_transition(Time::new(1.0), Color::transparent(), Easing::Linear);
+    assert_eq!(screen.actual_color.a, 0.5);
+    screen.time_spent(Time::new(0.5));
+    assert_eq!(screen.actual_color.a, 0.25);
+
+    screen.flash(Time::new(1.0), Color::white());
+    screen.quake(4.0, Time::new(1.0));
+    screen.time_spent(Time::new(0.75));
+    assert_eq!(screen.flash_color(), Some(Color::new(1.0, 1.0, 1.0, 0.25)));
+    let (x, y) = screen.quake_offset();
+    assert!(x.abs() <= 1.0 && y.abs() <= 1.0);
+    screen.time_spent(Time::new(0.25));
+    assert_eq!(screen.flash_color(), None);
+    assert_eq!(screen.quake_offset(), (0.0, 0.0));
+    assert_eq!(screen.actual_color, Color::transparent());
+
+    screen.quake(4.0, Time::new(1.0));
+    screen.quake(4.0, Time::new(0.0));
+    assert_eq!(screen.quake_offset(), (0.0, 0.0));
+}
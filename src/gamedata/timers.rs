@@ -0,0 +1,100 @@
+use crate::gamedata::{Time, TIME_EPSILON};
+use crate::WakeFlag;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// A `WakeFlag` to wake at a given time
+#[derive(Debug)]
+struct Timer {
+    /// The time of the clock at which it is woken, in second
+    deadline: f64,
+    /// The number of timers added before this one, so timers with the same deadline are woken in the order they were added
+    sequence: u64,
+    flag: WakeFlag,
+}
+
+impl PartialEq for Timer {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Timer {}
+
+impl PartialOrd for Timer {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Timer {
+    /// Reversed, so the ``BinaryHeap`` give the earliest timer first
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .deadline
+            .total_cmp(&self.deadline)
+            .then(other.sequence.cmp(&self.sequence))
+    }
+}
+
+/// The `WakeFlag`s waiting for some time to pass, ordered by deadline, so advancing the clock only look at the ones
+/// that are woken.
+#[derive(Debug, Default)]
+pub struct Timers {
+    clock: Time,
+    next_sequence: u64,
+    timers: BinaryHeap<Timer>,
+}
+
+impl Timers {
+    /// Wake the flag once duration passed
+    pub fn add(&mut self, duration: Time, flag: WakeFlag) {
+        self.timers.push(Timer {
+            deadline: self.clock.get_time() + duration.get_time(),
+            sequence: self.next_sequence,
+            flag,
+        });
+        self.next_sequence += 1;
+    }
+
+    /// Advance the clock, waking the flags which deadline is reached
+    pub fn time_spent(&mut self, time: Time) {
+        self.clock += time;
+        while let Some(timer) = self.timers.peek() {
+            if timer.deadline > self.clock.get_time() + TIME_EPSILON {
+                break;
+            };
+            self.timers.pop().unwrap().flag.wake();
+        }
+    }
+
+    /// The number of flags not yet woken
+    pub fn len(&self) -> usize {
+        self.timers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.timers.is_empty()
+    }
+}
+
+#[test]
+fn test_timers() {
+    let mut timers = Timers::default();
+    let late = WakeFlag::new();
+    let early = WakeFlag::new();
+    timers.add(Time::new(1.0), late.clone());
+    timers.add(Time::new(0.5), early.clone());
+    timers.time_spent(Time::new(0.25));
+    assert!(!early.is_woken());
+    timers.time_spent(Time::new(0.25));
+    assert!(early.is_woken());
+    assert!(!late.is_woken());
+    assert_eq!(timers.len(), 1);
+    // 60 frames of 1/60 second last exactly one second
+    for _ in 0..30 {
+        timers.time_spent(Time::from_frames(1, 60));
+    }
+    assert!(late.is_woken());
+    assert!(timers.is_empty());
+}
@@ -1,4 +1,7 @@
-use crate::gamedata::{Color, Lock, Portrait, Speed, Time, Vec2_f64, Vec3_f64};
+use crate::gamedata::{
+    Color, Easing, Lock, Menu, Message, Motion, MoveKind, PadState, Portrait, Speed, Time,
+    Vec2_f64, Vec3_f64,
+};
 
 /// This enum store everything that can update the scene
 #[derive(Debug, Clone)]
@@ -11,18 +14,58 @@ pub enum Update {
     SetPosition(String, Vec3_f64),
     /// id, posititon, speed
     WalkTo(String, Vec2_f64, Speed),
+    /// id, the points to go through, speed, how to move, how the speed change along the path
+    MoveAlong(String, Vec<Vec2_f64>, Speed, MoveKind, Easing),
+    /// id, angle (in radians), immediate
+    SetAngle(String, f64),
+    /// id, angle (in radians), duration of the turn
+    TurnTo(String, f64, Time),
+    /// id, height above the current height, duration of the jump
+    Jump(String, f64, Time),
+    /// id, height (the z coordinate), duration of the change (immediate if null)
+    SetHeight(String, f64, Time, Easing),
     /// Lock
     AddLock(Lock),
     /// time spent
     TimeSpent(Time),
+    /// Move the focus of the camera to the position in the duration (immediately if null), and stop following
+    MoveCamera(Vec2_f64, Time, Easing),
+    /// Keep the focus of the camera on the character (by it's id), or stop following with ``None``
+    FollowChara(Option<String>),
+    /// Change the zoom of the camera in the duration (immediately if null)
+    ZoomCamera(f64, Time, Easing),
+    /// Rotate the camera to the angle (in radians) in the duration (immediately if null)
+    RotateCamera(f64, Time, Easing),
     /// Set the color of a screen (by it's id), immediate
     SetScreenColor(u16, Color),
     /// Do a transition of the color of a screen (by it's id)
-    TransitionScreenColor(u16, Time, Color),
-    /// The pokemeon finished everything, can start the IDLE animation
+    TransitionScreenColor(u16, Time, Color, Easing),
+    /// Do a transition of the tint of a screen (by it's id)
+    TransitionScreenTint(u16, Time, Color, Easing),
+    /// Cover a screen (by it's id) with the color, fading out in the duration
+    FlashScreen(u16, Time, Color),
+    /// Shake a screen (by it's id), with the amplitude in pixel, for the duration
+    QuakeScreen(u16, f64, Time),
+    /// The pokemeon stopped walking, and start the IDLE animation
     StartIDLE(String),
+    /// id, the animation to play
+    SetMotion(String, Motion),
+    /// The animation played once by the character (by it's id) finished, it come back to the IDLE animation
+    MotionFinished(String),
     /// Display a face on the screen
     SetPortrait(Portrait),
     /// Remove the displayed face
     RemovePortrait,
+    /// Display a message in the text box, replacing the previous one
+    ShowMessage(Message),
+    /// Close the text box
+    CloseMessage,
+    /// Display a menu, until an option is choosen
+    ShowMenu(Menu),
+    /// The state of the buttons for this frame
+    Pad(PadState),
+    /// Raise a signal (by it's name), releasing the tasks waiting for it
+    RaiseSignal(String),
+    /// Lower a raised signal (by it's name), so it can be waited again
+    ClearSignal(String),
 }
@@ -2,8 +2,12 @@ use mlua::{FromLua, UserData};
 use std::cmp::Ordering;
 use std::ops::{AddAssign, SubAssign};
 
+/// Two times closer than this are considered equal, in second, to ignore rounding errors.
+/// Waiting 60 frames of 1/60 second thus last exactly 60 frames.
+pub const TIME_EPSILON: f64 = 1e-9;
+
 /// time, in second
-#[derive(Debug, Clone, PartialEq, Copy, FromLua)]
+#[derive(Debug, Clone, PartialEq, Copy, Default, FromLua)]
 pub struct Time(pub f64);
 
 impl Time {
@@ -11,6 +15,16 @@ impl Time {
         Self(duration)
     }
 
+    /// Create a new `Time` lasting frames frames, at framerate frames per second
+    pub fn from_frames(frames: u64, framerate: u32) -> Self {
+        Self(frames as f64 / framerate as f64)
+    }
+
+    /// Return the number of whole frames in this `Time`, at framerate frames per second
+    pub fn to_frames(self, framerate: u32) -> u64 {
+        (self.0 * framerate as f64 + TIME_EPSILON).floor().max(0.0) as u64
+    }
+
     /// Return the time contained by this structure, in second
     pub fn get_time(self) -> f64 {
         self.0
@@ -48,3 +62,14 @@ fn test_time() {
     time -= Time::new(3.0);
     assert_eq!(time.get_time(), 2.0 - 3.0);
 }
+
+#[test]
+fn test_time_frames() {
+    let mut time = Time::new(0.0);
+    for _ in 0..60 {
+        time += Time::from_frames(1, 60);
+    }
+    assert_eq!(time.to_frames(60), 60);
+    assert_eq!(time.to_frames(30), 30);
+    assert_eq!(Time::new(0.049).to_frames(60), 2);
+}
@@ -1,7 +1,9 @@
-use mlua::{FromLua, UserData};
+use mlua::{FromLua, UserData, UserDataFields};
+use serde::Deserialize;
 use std::ops::{Add, Div, Mul, Sub};
 
-#[derive(Copy, Clone, Debug, PartialEq, Default, FromLua)]
+#[derive(Copy, Clone, Debug, PartialEq, Default, FromLua, Deserialize)]
+#[serde(default)]
 #[allow(non_camel_case_types)]
 pub struct Vec3_f64 {
     pub x: f64,
@@ -9,7 +11,13 @@ pub struct Vec3_f64 {
     pub z: f64,
 }
 
-impl UserData for Vec3_f64 {}
+impl UserData for Vec3_f64 {
+    fn add_fields<F: UserDataFields<Self>>(fields: &mut F) {
+        fields.add_field_method_get("x", |_, this| Ok(this.x));
+        fields.add_field_method_get("y", |_, this| Ok(this.y));
+        fields.add_field_method_get("z", |_, this| Ok(this.z));
+    }
+}
 
 impl Vec3_f64 {
     pub fn new(x: f64, y: f64, z: f64) -> Self {
@@ -85,7 +93,12 @@ pub struct Vec2_f64 {
     pub y: f64,
 }
 
-impl UserData for Vec2_f64 {}
+impl UserData for Vec2_f64 {
+    fn add_fields<F: UserDataFields<Self>>(fields: &mut F) {
+        fields.add_field_method_get("x", |_, this| Ok(this.x));
+        fields.add_field_method_get("y", |_, this| Ok(this.y));
+    }
+}
 
 impl Vec2_f64 {
     pub fn distance(&self, other: &Vec2_f64) -> f64 {
@@ -100,6 +113,11 @@ impl Vec2_f64 {
         }
     }
 
+    /// The angle of this vector, in radians. 0 point to the right, PI/2 to the top.
+    pub fn angle(&self) -> f64 {
+        self.y.atan2(self.x)
+    }
+
     pub fn to_vec3(&self, z: f64) -> Vec3_f64 {
         Vec3_f64 {
             x: self.x,
@@ -1,10 +1,21 @@
+use crate::gamedata::PadState;
+
 #[derive(Default)]
 pub struct Input {
     pub time_elapsed: f64,
+    /// The buttons and direction inputed by the player
+    pub pad: PadState,
+    /// The id of the characters which motion played once finished since the previous frame. ``None`` if the
+    /// animations aren't played, like without display: those motions are then considered finished immediately.
+    pub finished_motions: Option<Vec<String>>,
 }
 
 impl Input {
     pub fn new(time: f64) -> Input {
-        Input { time_elapsed: time }
+        Input {
+            time_elapsed: time,
+            pad: PadState::default(),
+            finished_motions: None,
+        }
     }
 }
@@ -2,20 +2,34 @@
 extern crate log;
 
 mod lua;
-pub use lua::{add_locking_function, LockReason, RunningLua, YieldResult};
+pub use lua::{
+    add_locking_function, LockReason, ResumeValue, RunningLua, TaskManager, YieldResult,
+};
 
 pub mod gamedata;
 
+mod wakeflag;
+pub use wakeflag::WakeFlag;
+
 mod logic;
-pub use logic::Logic;
+pub use logic::{ClockMode, Logic, GAME_FRAMERATE};
 
 pub mod luaapi;
 
 mod input;
 pub use input::Input;
 
-mod transform_script;
-pub use transform_script::transform_script;
-
 mod render;
-pub use render::PistonRenderer;
+pub use render::{
+    execute_frame, HeadlessRenderer, OfflineError, OfflineOutput, OfflineRenderer, PistonRenderer,
+    SpeciesRegistry, UpdateReceiver,
+};
+
+mod loaderror;
+pub use loaderror::LoadError;
+
+mod scripterror;
+pub use scripterror::{ErrorPolicy, ScriptError, SCRIPT_CHUNK_NAME};
+
+mod scriptsource;
+pub use scriptsource::{convert_bytecode, RomfsScriptSource, ScriptCode, ScriptSourceError};
@@ -0,0 +1,43 @@
+use crate::ScriptError;
+use pmd_cpack::CPackError;
+use std::error::Error;
+use std::fmt;
+
+/// An error that happened while loading a script and the data it need
+#[derive(Debug)]
+pub enum LoadError {
+    /// The game data (like ``MONSTER/m_ground.bin``) couldn't be read
+    Data(CPackError),
+    /// The script couldn't be loaded
+    Script(ScriptError),
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Data(err) => Some(err),
+            Self::Script(err) => Some(err),
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Data(err) => write!(f, "impossible to load the game data: {}", err),
+            Self::Script(err) => write!(f, "impossible to load the script: {}", err),
+        }
+    }
+}
+
+impl From<CPackError> for LoadError {
+    fn from(err: CPackError) -> Self {
+        Self::Data(err)
+    }
+}
+
+impl From<ScriptError> for LoadError {
+    fn from(err: ScriptError) -> Self {
+        Self::Script(err)
+    }
+}
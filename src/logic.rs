@@ -1,37 +1,138 @@
-use crate::gamedata::{Scene, Time, Update};
+use crate::gamedata::{PadButton, PadState, Scene, Time, Update, TIME_EPSILON};
 use crate::luaapi::initialize_lua_environment;
-use crate::transform_script;
 use crate::Input;
-use crate::RunningLua;
+use crate::{ErrorPolicy, RunningLua, ScriptError};
+use std::num::NonZeroU32;
 use std::sync::{Arc, Mutex};
 
+/// The number of frames per second of the games
+pub const GAME_FRAMERATE: NonZeroU32 = NonZeroU32::new(60).unwrap();
+
+/// The most ticks run by a single `Logic::execute` with `ClockMode::Fixed`. After a longer frame (like when the window
+/// was frozen), the late time is dropped, so the scene slow down instead of trying to catch up.
+pub const MAX_TICKS_PER_EXECUTE: u32 = 2 * GAME_FRAMERATE.get();
+
+/// How the time of the scene advance
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ClockMode {
+    /// Advance by the time elapsed given by each `Input`
+    #[default]
+    Variable,
+    /// Advance by ticks lasting one frame at this framerate, like the frame counter of the games. The elapsed time
+    /// is accumulated until a whole tick passed, so the scene evolve the same way whatever the rendering framerate.
+    Fixed(NonZeroU32),
+}
+
 /// Store everything related to the logic of this library, cf not related to rendering.
 /// This include scene data, and lua execution
 #[derive(Debug)]
 pub struct Logic {
     lua: RunningLua,
     pub scene: Arc<Mutex<Scene>>,
+    clock_mode: ClockMode,
+    /// The time elapsed that isn't yet used by a tick, with `ClockMode::Fixed`
+    accumulated_time: Time,
+    /// The pad state for the next tick, with `ClockMode::Fixed`. Keep the buttons pressed during frames without tick.
+    pending_pad: PadState,
+    /// The renderer play the animations, and tell when the motions played once finish (see `Input::finished_motions`)
+    animations_played: bool,
+    /// The characters whose motion played once finished, reported by the renderer, to apply at the next tick
+    pending_finished_motions: Vec<String>,
 }
 
 impl Logic {
-    /// Create a new `Logic` struct, that will execute the lua script inputed
-    pub fn new(script: &str) -> Self {
+    /// Create a new `Logic` struct, that will execute the lua script inputed. Return an error if the script can't be compiled.
+    pub fn new<S: AsRef<[u8]> + ?Sized>(script: &S) -> Result<Self, ScriptError> {
         let mut lua = RunningLua::default();
         let scene = Arc::new(Mutex::new(Scene::default()));
         initialize_lua_environment(&lua, &scene);
-        lua.load_script(&transform_script(script));
-        Logic { lua, scene }
+        lua.load_script(script)?;
+        Ok(Logic {
+            lua,
+            scene,
+            clock_mode: ClockMode::default(),
+            accumulated_time: Time::new(0.0),
+            pending_pad: PadState::default(),
+            animations_played: false,
+            pending_finished_motions: Vec::new(),
+        })
+    }
+
+    /// Choose how the time of the scene advance. Default to `ClockMode::Variable`.
+    pub fn set_clock_mode(&mut self, clock_mode: ClockMode) {
+        self.clock_mode = clock_mode;
+        self.accumulated_time = Time::new(0.0);
     }
 
     /// Execute the lua code until it need to wait for further stuff. You are supposed to call this function once a frame
-    pub fn execute(&mut self, input: Input) {
+    ///
+    /// With `ClockMode::Fixed`, this run as many ticks as the elapsed time allow, possibly none, up to `MAX_TICKS_PER_EXECUTE`.
+    /// Return the first error raised by the script during this frame.
+    pub fn execute(&mut self, input: Input) -> Result<(), ScriptError> {
+        self.animations_played = input.finished_motions.is_some();
+        // applied by the next tick, so they are reported at the same tick whatever the framerate
+        if let Some(finished_motions) = input.finished_motions {
+            self.pending_finished_motions.extend(finished_motions);
+        };
+        let framerate = match self.clock_mode {
+            ClockMode::Variable => return self.tick(Time::new(input.time_elapsed), input.pad),
+            ClockMode::Fixed(framerate) => framerate,
+        };
+        for button in &input.pad.pressed {
+            self.pending_pad.press(*button);
+        }
+        self.pending_pad.held = input.pad.held;
+        self.pending_pad.direction = input.pad.direction;
+        self.accumulated_time += Time::new(input.time_elapsed);
+        let tick_duration = Time::from_frames(1, framerate.get());
+        let mut ticks = 0;
+        while self.accumulated_time.get_time() >= tick_duration.get_time() - TIME_EPSILON {
+            if ticks == MAX_TICKS_PER_EXECUTE {
+                self.accumulated_time = Time::new(0.0);
+                break;
+            };
+            ticks += 1;
+            self.accumulated_time -= tick_duration;
+            let pad = self.pending_pad.clone();
+            self.pending_pad.new_frame();
+            self.tick(tick_duration, pad)?;
+        }
+        Ok(())
+    }
+
+    /// Advance the scene by time_elapsed, then run the lua code
+    fn tick(&mut self, time_elapsed: Time, pad: PadState) -> Result<(), ScriptError> {
         {
             let mut lock = self.scene.lock().unwrap();
-            lock.update(Update::TimeSpent(Time::new(input.time_elapsed)));
+            for id in self.pending_finished_motions.drain(..) {
+                lock.update(Update::MotionFinished(id));
+            }
+            if !self.animations_played {
+                for id in lock.playing_one_shot_motions() {
+                    lock.update(Update::MotionFinished(id));
+                }
+            };
+            lock.update(Update::TimeSpent(time_elapsed));
+            lock.update(Update::Pad(pad));
         }
         self.lua.execute()
     }
 
+    /// Choose what happen when a lua thread fail
+    pub fn set_error_policy(&mut self, error_policy: ErrorPolicy) {
+        self.lua.set_error_policy(error_policy);
+    }
+
+    /// Return ``true`` if every lua thread has finished its execution
+    pub fn is_finished(&self) -> bool {
+        self.lua.is_finished()
+    }
+
+    /// Return a button the script is waiting the player to press, if any
+    pub fn expected_button(&self) -> Option<PadButton> {
+        self.scene.lock().unwrap().expected_button()
+    }
+
     /// Return the list of `Update` from the last execution. Also empty it, so don't call it two time consecutively.
     pub fn get_and_clear_updates(&mut self) -> Vec<Update> {
         let mut lock = self.scene.lock().unwrap();
@@ -41,16 +142,552 @@ impl Logic {
 
 #[test]
 fn test_logic() {
-    let mut logic = Logic::new("OBJECT_DynamicLoad(CHARA, \"HERO\", \"KIBAGO\")");
-    logic.execute(Input::default());
+    let mut logic = Logic::new("OBJECT_DynamicLoad(CHARA, \"HERO\", \"KIBAGO\")").unwrap();
+    logic.execute(Input::default()).unwrap();
 
-    match &logic.get_and_clear_updates()[0] {
-        Update::AddChara(id, specie) => {
-            assert_eq!(&*id, "HERO");
-            assert_eq!(&*specie, "KIBAGO");
-        }
-        _ => (),
+    if let Update::AddChara(id, specie) = &logic.get_and_clear_updates()[0] {
+        assert_eq!(id, "HERO");
+        assert_eq!(specie, "KIBAGO");
     };
 
     assert!(logic.get_and_clear_updates().is_empty());
 }
+
+#[test]
+fn test_logic_method_call_syntax() {
+    let mut logic = Logic::new(
+        "--[[ a long comment, with CH(\"A\"):WaitMove()
+        ]]
+        local text = \"a:b -- not a comment\"
+        CHARA:DynamicLoad(
+            \"HERO\",
+            (\"kibago\"):upper()
+        )
+        TASK:Sleep(TimeSec(0.5)) CH(\"HERO\"):SetPosition(Vector(1, 2, 0))
+        CH(\"HERO\"):WalkTo(Vector2(1, 3), Speed(1)) CH(\"HERO\"):WaitMove()
+        CHARA:DynamicRemove(text)
+        CH(\"HERO\"):Explode()",
+    )
+    .unwrap();
+    let mut updates = Vec::new();
+    let mut error = None;
+    for _ in 0..20 {
+        if let Err(err) = logic.execute(Input::new(0.2)) {
+            error = Some(err);
+            break;
+        };
+        updates.extend(logic.get_and_clear_updates());
+    }
+    updates.extend(logic.get_and_clear_updates());
+
+    assert!(updates.iter().any(
+        |update| matches!(update, Update::AddChara(id, specie) if id == "HERO" && specie == "KIBAGO")
+    ));
+    assert!(updates
+        .iter()
+        .any(|update| matches!(update, Update::DelChara(id) if id == "a:b -- not a comment")));
+    assert_eq!(error.unwrap().line, Some(11));
+}
+
+#[test]
+fn test_logic_blocking_method_table_hidden() {
+    // the blocking methods doesn't depend on a global the script could overwrite
+    let mut logic = Logic::new(
+        "
+        _yammy_blocking_method = nil
+        TASK:Sleep(TimeSec(0.1))
+        done = true",
+    )
+    .unwrap();
+    logic.execute(Input::new(0.0)).unwrap();
+    logic.execute(Input::new(0.2)).unwrap();
+    assert!(logic.lua.lua().globals().get::<bool>("done").unwrap());
+}
+
+#[test]
+fn test_logic_pad() {
+    use crate::gamedata::PadState;
+    let mut logic = Logic::new(
+        "PAD:WaitButton(PAD_BUTTON.B)
+        if PAD:IsHeld(PAD_BUTTON.B) and PAD:GetDirection().x == 1 then
+            CHARA:DynamicLoad(\"HERO\", \"KIBAGO\")
+        end",
+    )
+    .unwrap();
+    logic.execute(Input::default()).unwrap();
+    assert_eq!(logic.expected_button(), Some(PadButton::B));
+    let mut pad = PadState::default();
+    pad.press(PadButton::B);
+    pad.press(PadButton::RIGHT);
+    pad.set_direction_from_dpad();
+    logic
+        .execute(Input {
+            time_elapsed: 0.0,
+            pad,
+            ..Input::default()
+        })
+        .unwrap();
+    assert!(logic.is_finished());
+    assert!(logic
+        .get_and_clear_updates()
+        .iter()
+        .any(|update| matches!(update, Update::AddChara(id, _) if id == "HERO")));
+}
+
+#[test]
+fn test_logic_select_choice() {
+    let mut logic = Logic::new(
+        "local choice = WINDOW:SelectChoice({\"Yes\", \"No\"})
+        CHARA:DynamicLoad(\"HERO\", choice == 2 and \"KIBAGO\" or \"TSUTAAJA\")",
+    )
+    .unwrap();
+    logic.execute(Input::default()).unwrap();
+    for button in [PadButton::DOWN, PadButton::A] {
+        assert!(!logic.is_finished());
+        let mut input = Input::default();
+        input.pad.press(button);
+        logic.execute(input).unwrap();
+    }
+    assert!(logic.is_finished());
+    assert!(logic.scene.lock().unwrap().menu.is_none());
+    assert!(logic
+        .get_and_clear_updates()
+        .iter()
+        .any(|update| matches!(update, Update::AddChara(_, actor) if actor == "KIBAGO")));
+}
+
+#[test]
+fn test_logic_fixed_clock_determinism() {
+    let script = "CHARA:DynamicLoad(\"HERO\", \"KIBAGO\")
+        CHARA:DynamicLoad(\"PARTNER\", \"TSUTAAJA\")
+        CH(\"HERO\"):WalkTo(Vector2(2, 0), Speed(1.3))
+        CH(\"PARTNER\"):WalkTo(Vector2(0, 3), Speed(2))
+        TASK:Sleep(TimeSec(0.5))
+        SCREEN_A:FadeOut(TimeSec(0.7), false)
+        CH(\"HERO\"):WaitMove()
+        CH(\"PARTNER\"):WaitMove()
+        TASK:Sleep(TimeSec(1))";
+    // the same total time, split differently
+    let run = |frame_durations: &[f64]| {
+        let mut logic = Logic::new(script).unwrap();
+        logic.set_clock_mode(ClockMode::Fixed(GAME_FRAMERATE));
+        let mut updates = Vec::new();
+        let mut frame = 0;
+        while !logic.is_finished() {
+            logic
+                .execute(Input::new(frame_durations[frame % frame_durations.len()]))
+                .unwrap();
+            updates.extend(logic.get_and_clear_updates());
+            frame += 1;
+        }
+        // the last frame may contain more ticks, after the end of the script
+        while let Some(Update::TimeSpent(_) | Update::Pad(_) | Update::StartIDLE(_)) =
+            updates.last()
+        {
+            updates.pop();
+        }
+        format!("{:?}", updates)
+    };
+    let reference = run(&[1.0 / 60.0]);
+    assert_eq!(reference, run(&[1.0 / 144.0, 1.0 / 61.0, 1.0 / 30.0]));
+    assert_eq!(reference, run(&[0.1, 0.0]));
+}
+
+#[test]
+fn test_logic_tasks() {
+    let mut logic = Logic::new(
+        "
+        log = \"\"
+        local slow = TASK:Regist(function()
+            TASK:Sleep(TimeSec(1))
+            log = log .. \"slow,\"
+        end)
+        local fast = TASK:Regist(function()
+            TASK:Sleep(TimeSec(0.5))
+            log = log .. \"fast,\"
+        end, \"fast\")
+        local forever = TASK:Regist(function()
+            while true do
+                TASK:Sleep(TimeSec(0.1))
+            end
+        end)
+        assert(TASK:Find(\"fast\") == fast and TASK:Find(\"none\") == nil)
+        TASK:WaitTask(fast)
+        log = log .. tostring(TASK:IsRunning(fast)) .. tostring(TASK:IsRunning(slow)) .. \",\"
+        TASK:Kill(forever)
+        TASK:WaitAllTasks({slow, forever, fast})
+        log = log .. tostring(TASK:IsRunning(forever)) .. tostring(TASK:Kill(forever))",
+    )
+    .unwrap();
+    logic.set_clock_mode(ClockMode::Fixed(GAME_FRAMERATE));
+    logic.execute(Input::new(0.6)).unwrap();
+    assert_eq!(
+        logic.lua.lua().globals().get::<String>("log").unwrap(),
+        "fast,falsetrue,"
+    );
+    logic.execute(Input::new(0.6)).unwrap();
+    assert_eq!(
+        logic.lua.lua().globals().get::<String>("log").unwrap(),
+        "fast,falsetrue,slow,falsefalse"
+    );
+    assert!(logic.is_finished());
+}
+
+#[test]
+fn test_logic_wait_any() {
+    let mut logic = Logic::new(
+        "
+        result = yammy_wait(yammy_wait_any(TASK:_Sleep(TimeSec(1)), WINDOW:_SelectChoice({\"a\", \"b\"})))
+        timed_out = true
+        TASK:Sleep(TimeSec(1))",
+    )
+    .unwrap();
+    logic.set_clock_mode(ClockMode::Fixed(GAME_FRAMERATE));
+    logic.execute(Input::new(0.5)).unwrap();
+    assert!(logic.scene.lock().unwrap().menu.is_some());
+    // no choice was made before the timeout
+    logic.execute(Input::new(0.6)).unwrap();
+    assert!(logic.lua.lua().globals().get::<bool>("timed_out").unwrap());
+    assert!(logic
+        .lua
+        .lua()
+        .globals()
+        .get::<Option<i64>>("result")
+        .unwrap()
+        .is_none());
+    // the choice that lost was cancelled, so nothing wait for the player anymore
+    let scene = logic.scene.lock().unwrap();
+    assert!(scene.menu.is_none());
+    assert_eq!(scene.expected_button(), None);
+}
+
+#[test]
+fn test_logic_removed_chara() {
+    let mut logic = Logic::new(
+        "
+        CHARA:DynamicLoad(\"HERO\", \"KIBAGO\")
+        TASK:Regist(function()
+            CH(\"HERO\"):WalkTo(Vector2(100, 0), Speed(1))
+            CH(\"HERO\"):WaitMove()
+            released = true
+        end)
+        TASK:Sleep(TimeSec(0.1))
+        CHARA:DynamicRemove(\"HERO\")
+        TASK:Sleep(TimeSec(0.1))
+        CH(\"HERO\"):SetDir(DIR.UP)",
+    )
+    .unwrap();
+    logic.set_clock_mode(ClockMode::Fixed(GAME_FRAMERATE));
+    let error = logic.execute(Input::new(0.5)).unwrap_err();
+    assert_eq!(error.line, Some(11));
+    // the task waiting for the removed character was released, and the scene is still usable
+    let released: bool = logic.lua.lua().globals().get("released").unwrap();
+    assert!(released);
+    assert!(logic.scene.lock().unwrap().charas.is_empty());
+}
+
+#[test]
+fn test_logic_turn() {
+    use std::f64::consts::PI;
+    let mut logic = Logic::new(
+        "
+        CHARA:DynamicLoad(\"HERO\", \"KIBAGO\")
+        CHARA:DynamicLoad(\"PARTNER\", \"TSUTAAJA\")
+        CH(\"PARTNER\"):SetPosition(Vector(2, 2, 0))
+        CH(\"HERO\"):SetDir(DIR.DOWN)
+        CH(\"PARTNER\"):DirTo(Vector2(1, 2))
+        CH(\"HERO\"):TurnToChara(\"PARTNER\", TimeSec(0.5))
+        CH(\"HERO\"):WaitTurn()
+        CH(\"PARTNER\"):TurnTo(DIR.DOWN_RIGHT, TimeSec(1))",
+    )
+    .unwrap();
+    logic.set_clock_mode(ClockMode::Fixed(GAME_FRAMERATE));
+    logic.execute(Input::new(0.25)).unwrap();
+    {
+        let scene = logic.scene.lock().unwrap();
+        assert_eq!(scene.charas["PARTNER"].angle, PI);
+        // turned the shortest way, through the right
+        let angle = scene.charas["HERO"].angle.rem_euclid(2.0 * PI);
+        assert!((angle - 1.875 * PI).abs() < 0.1);
+    }
+    assert!(!logic.is_finished());
+    logic.execute(Input::new(0.3)).unwrap();
+    assert!(logic.is_finished());
+    let scene = logic.scene.lock().unwrap();
+    let angle = scene.charas["HERO"].angle.rem_euclid(2.0 * PI);
+    assert!((angle - 0.25 * PI).abs() < 1e-9);
+    assert!(scene.charas["PARTNER"].is_turning());
+}
+
+#[test]
+fn test_logic_height() {
+    let mut logic = Logic::new(
+        "
+        CHARA:DynamicLoad(\"HERO\", \"KIBAGO\")
+        CH(\"HERO\"):SetHeight(3)
+        CH(\"HERO\"):Jump(1, TimeSec(0.5))
+        CH(\"HERO\"):WalkTo(Vector2(1, 0), Speed(1))
+        CH(\"HERO\"):WaitJump()
+        CH(\"HERO\"):SetHeight(0, TimeSec(0.5))
+        CH(\"HERO\"):WaitHeight()",
+    )
+    .unwrap();
+    logic.set_clock_mode(ClockMode::Fixed(GAME_FRAMERATE));
+    logic.execute(Input::new(0.3)).unwrap();
+    {
+        let scene = logic.scene.lock().unwrap();
+        // in the air, while moving forward
+        assert!(scene.charas["HERO"].position.z > 3.5);
+        assert!(scene.charas["HERO"].position.x > 0.0);
+    }
+    logic.execute(Input::new(0.5)).unwrap();
+    {
+        let scene = logic.scene.lock().unwrap();
+        // landed, and falling
+        assert!(scene.charas["HERO"].position.z < 3.0);
+    }
+    assert!(!logic.is_finished());
+    logic.execute(Input::new(0.3)).unwrap();
+    assert!(logic.is_finished());
+    assert_eq!(logic.scene.lock().unwrap().charas["HERO"].position.z, 0.0);
+}
+
+#[test]
+fn test_logic_easing() {
+    let mut logic = Logic::new(
+        "
+        CHARA:DynamicLoad(\"HERO\", \"KIBAGO\")
+        CHARA:DynamicLoad(\"PARTNER\", \"TSUTAAJA\")
+        CH(\"HERO\"):WalkTo(Vector2(2, 0), Speed(1), EASE.EASE_IN)
+        CH(\"PARTNER\"):MovePath({Vector2(0, 1), Vector2(0, 2)}, Speed(1), MOVE.RUN, EASE.EASE_OUT)
+        SCREEN_A:FadeOut(TimeSec(2), false, EASE.SINE)
+        CH(\"HERO\"):WaitMove()",
+    )
+    .unwrap();
+    logic.set_clock_mode(ClockMode::Fixed(GAME_FRAMERATE));
+    logic.execute(Input::new(1.0)).unwrap();
+    {
+        let scene = logic.scene.lock().unwrap();
+        // halfway through the time, the one easing in is behind, the one easing out ahead
+        assert!(scene.charas["HERO"].position.x < 0.6);
+        assert!(scene.charas["PARTNER"].position.y > 1.4);
+        let alpha = scene.screens[0].actual_color.a;
+        assert!((alpha - 0.5).abs() < 0.05);
+    }
+    logic.execute(Input::new(1.1)).unwrap();
+    assert!(logic.is_finished());
+    let scene = logic.scene.lock().unwrap();
+    assert_eq!(scene.charas["HERO"].position.x, 2.0);
+    assert_eq!(scene.charas["PARTNER"].position.y, 2.0);
+    assert_eq!(
+        scene.screens[0].actual_color,
+        crate::gamedata::Color::black()
+    );
+}
+
+#[test]
+fn test_logic_camera() {
+    use crate::gamedata::Vec2_f64;
+    let mut logic = Logic::new(
+        "
+        CHARA:DynamicLoad(\"HERO\", \"KIBAGO\")
+        CAMERA:SetEye(Vector(1, 1, 0))
+        CAMERA:MoveTo(Vector2(3, 1), TimeSec(1))
+        CAMERA:SetZoom(2, TimeSec(0.5))
+        CAMERA:SetRotation(90)
+        CAMERA:WaitMove()
+        CAMERA:FollowChara(\"HERO\")
+        CH(\"HERO\"):WalkTo(Vector2(0, 2), Speed(4))
+        CH(\"HERO\"):WaitMove()",
+    )
+    .unwrap();
+    logic.set_clock_mode(ClockMode::Fixed(GAME_FRAMERATE));
+    logic.execute(Input::new(0.6)).unwrap();
+    {
+        let scene = logic.scene.lock().unwrap();
+        assert!(scene.camera.focus.x > 1.9 && scene.camera.focus.x < 3.0);
+        assert_eq!(scene.camera.zoom, 2.0);
+        assert_eq!(scene.camera.angle, std::f64::consts::FRAC_PI_2);
+    }
+    logic.execute(Input::new(1.0)).unwrap();
+    assert!(logic.is_finished());
+    let scene = logic.scene.lock().unwrap();
+    // the camera moved with the character it follow
+    assert_eq!(scene.camera.focus, Vec2_f64::new(0.0, 2.0));
+}
+
+#[test]
+fn test_logic_screen_effects() {
+    use crate::gamedata::Color;
+    let mut logic = Logic::new(
+        "
+        SCREEN_A:FadeOut(TimeSec(0.5), true)
+        SCREEN_A:FadeIn(TimeSec(0.5), false)
+        SCREEN_A:SetTint(Color(1, 0, 0, 0.5), TimeSec(0), false)
+        SCREEN_A:Quake(3, TimeSec(1), false)
+        SCREEN_A:Flash(nil, TimeSec(0.5), true)
+        SCREEN_A:FadeToColor(Color(1, 1, 1), TimeSec(0.2), true)",
+    )
+    .unwrap();
+    logic.set_clock_mode(ClockMode::Fixed(GAME_FRAMERATE));
+    logic.execute(Input::new(0.6)).unwrap();
+    {
+        let scene = logic.scene.lock().unwrap();
+        let screen = &scene.screens[0];
+        // fading in after the fade out
+        assert!(screen.actual_color.a > 0.5 && screen.actual_color.a < 1.0);
+        assert_eq!(screen.tint, Color::new(1.0, 0.0, 0.0, 0.5));
+        assert!(screen.flash_color().is_some());
+        assert_ne!(screen.quake_offset(), (0.0, 0.0));
+    }
+    logic.execute(Input::new(0.5)).unwrap();
+    {
+        let scene = logic.scene.lock().unwrap();
+        let screen = &scene.screens[0];
+        // the flash ended, and the fade to white started from the faded in color
+        assert_eq!(screen.flash_color(), None);
+        let color = &screen.actual_color;
+        assert!(color.r > 0.0 && color.r < 1.0 && color.r == color.a);
+    }
+    logic.execute(Input::new(0.3)).unwrap();
+    assert!(logic.is_finished());
+    assert_eq!(
+        logic.scene.lock().unwrap().screens[0].actual_color,
+        Color::white()
+    );
+}
+
+#[test]
+fn test_logic_tick_limit() {
+    let mut logic = Logic::new(
+        "
+        TASK:Sleep(TimeSec(3))
+        done = true",
+    )
+    .unwrap();
+    logic.set_clock_mode(ClockMode::Fixed(GAME_FRAMERATE));
+    // only two seconds are run
+    logic.execute(Input::new(10.0)).unwrap();
+    assert!(!logic.is_finished());
+    logic.execute(Input::new(0.9)).unwrap();
+    assert!(!logic.is_finished());
+    logic.execute(Input::new(0.2)).unwrap();
+    assert!(logic.lua.lua().globals().get::<bool>("done").unwrap());
+}
+
+#[test]
+fn test_logic_motion() {
+    use crate::gamedata::Motion;
+    let script = "
+        CHARA:DynamicLoad(\"HERO\", \"KIBAGO\")
+        CH(\"HERO\"):SetMotion(MOTION.TALK)
+        CH(\"HERO\"):WaitMotion()
+        CH(\"HERO\"):PlayMotion(MOTION.NOD)
+        CH(\"HERO\"):WaitMotion()
+        CH(\"HERO\"):WalkTo(Vector2(0, 1), Speed(10))";
+    // the renderer tell when the motion finished
+    let mut logic = Logic::new(script).unwrap();
+    let with_animations = |finished: &[&str]| Input {
+        time_elapsed: 0.05,
+        finished_motions: Some(finished.iter().map(|id| id.to_string()).collect()),
+        ..Input::default()
+    };
+    logic.execute(with_animations(&[])).unwrap();
+    assert_eq!(
+        logic.scene.lock().unwrap().charas["HERO"].motion,
+        Motion::new("NOD", false)
+    );
+    logic.execute(with_animations(&[])).unwrap();
+    assert!(logic.scene.lock().unwrap().charas["HERO"]
+        .moving_kind()
+        .is_none());
+    logic.execute(with_animations(&["HERO"])).unwrap();
+    assert!(logic.is_finished());
+    assert_eq!(
+        logic.scene.lock().unwrap().charas["HERO"].motion,
+        Motion::idle()
+    );
+    // idle is started once, when the walk end
+    let mut start_idle = 0;
+    for _ in 0..10 {
+        logic.execute(with_animations(&[])).unwrap();
+        start_idle += logic
+            .get_and_clear_updates()
+            .iter()
+            .filter(|update| matches!(update, Update::StartIDLE(_)))
+            .count();
+    }
+    assert_eq!(start_idle, 1);
+
+    // without animation, the motions played once end immediately
+    let mut logic = Logic::new(script).unwrap();
+    logic.execute(Input::new(0.05)).unwrap();
+    logic.execute(Input::new(0.05)).unwrap();
+    assert!(logic.is_finished());
+
+    // with a fixed clock, a motion finished in a frame without tick is applied at the next tick
+    let mut logic = Logic::new(script).unwrap();
+    logic.set_clock_mode(ClockMode::Fixed(GAME_FRAMERATE));
+    for _ in 0..3 {
+        logic.execute(with_animations(&[])).unwrap();
+    }
+    let in_frame = |time_elapsed: f64, finished: &[&str]| Input {
+        time_elapsed,
+        ..with_animations(finished)
+    };
+    logic.execute(in_frame(0.001, &["HERO"])).unwrap();
+    assert_eq!(
+        logic.scene.lock().unwrap().charas["HERO"].motion,
+        Motion::new("NOD", false)
+    );
+    logic
+        .execute(in_frame(1.0 / GAME_FRAMERATE.get() as f64, &[]))
+        .unwrap();
+    assert_ne!(
+        logic.scene.lock().unwrap().charas["HERO"].motion,
+        Motion::new("NOD", false)
+    );
+}
+
+#[test]
+fn test_logic_move_path() {
+    use crate::gamedata::{MoveKind, Vec3_f64};
+    let mut logic = Logic::new(
+        "
+        CHARA:DynamicLoad(\"HERO\", \"KIBAGO\")
+        CH(\"HERO\"):MovePath({Vector2(1, 0), Vector(1, 1, 0), Vector2(0, 1)}, Speed(3), MOVE.RUN)
+        CH(\"HERO\"):WaitMove()
+        CH(\"HERO\"):MoveToRelative(Vector2(0, 2), Speed(4))
+        CH(\"HERO\"):WaitMove()
+        CH(\"HERO\"):SlideTo(Vector2(0, 0), Speed(6))
+        CH(\"HERO\"):WaitMove()",
+    )
+    .unwrap();
+    logic.set_clock_mode(ClockMode::Fixed(GAME_FRAMERATE));
+    let mut updates = Vec::new();
+    logic.execute(Input::new(0.5)).unwrap();
+    updates.extend(logic.get_and_clear_updates());
+    {
+        // on the second segment of the path, after 29 ticks of movement
+        let scene = logic.scene.lock().unwrap();
+        let hero = &scene.charas["HERO"];
+        assert_eq!(hero.moving_kind(), Some(MoveKind::Run));
+        assert_eq!(hero.position.x, 1.0);
+        assert!((hero.position.y - 0.45).abs() < 1e-9);
+    }
+    for _ in 0..4 {
+        logic.execute(Input::new(0.5)).unwrap();
+        updates.extend(logic.get_and_clear_updates());
+    }
+    assert!(logic.is_finished());
+    assert_eq!(
+        logic.scene.lock().unwrap().charas["HERO"].position,
+        Vec3_f64::new(0.0, 0.0, 0.0)
+    );
+    // the slide doesn't end with the idle animation
+    let start_idle = updates
+        .iter()
+        .filter(|update| matches!(update, Update::StartIDLE(_)))
+        .count();
+    assert_eq!(start_idle, 2);
+}
@@ -1,7 +1,14 @@
 //! This file contain everything required to make cooperative multiprocessing lua reader implementation
 #![allow(clippy::trivially_copy_pass_by_ref)]
-use mlua::{FromLua, Function, Lua, Nil, Table, Thread, ThreadStatus, UserData};
-use std::collections::HashMap;
+use crate::wakeflag::{ReadyTasks, TaskKey};
+use crate::{ErrorPolicy, ScriptError, WakeFlag, SCRIPT_CHUNK_NAME};
+use mlua::{
+    FromLua, Function, IntoLua, Lua, Nil, RegistryKey, Thread, ThreadStatus, UserData, Value,
+    Variadic,
+};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet};
+use std::mem::take;
 use std::sync::{
     atomic::{AtomicBool, Ordering::Relaxed},
     Arc, Mutex,
@@ -12,14 +19,25 @@ use std::sync::{
 pub enum LockReason {
     /// There is no reason for this ``Thread`` to be blocked
     None,
-    /// The ``Thread`` wait the ``AtomicBool`` to be true
+    /// The ``Thread`` wait the ``AtomicBool`` to be true. As it can't notify the scheduler, it is checked at each
+    /// pass: prefer `LockReason::WaitFlag`.
     WaitAtomicBool(Arc<AtomicBool>),
+    /// The ``Thread`` wait the `WakeFlag` to be woken
+    WaitFlag(WakeFlag),
+    /// The ``Thread`` wait for any of those `LockReason`s
+    Any(Vec<LockReason>),
+    /// The ``Thread`` wait for all of those `LockReason`s
+    All(Vec<LockReason>),
 }
 
 impl LockReason {
     pub fn new_abool(abool: Arc<AtomicBool>) -> LockReason {
         LockReason::WaitAtomicBool(abool)
     }
+
+    pub fn new_flag(flag: WakeFlag) -> LockReason {
+        LockReason::WaitFlag(flag)
+    }
 }
 
 impl LockReason {
@@ -28,6 +46,91 @@ impl LockReason {
         match self {
             Self::None => true,
             Self::WaitAtomicBool(value) => value.load(Relaxed),
+            Self::WaitFlag(flag) => flag.is_woken(),
+            Self::Any(reasons) => reasons.iter().any(|reason| reason.can_continue()),
+            Self::All(reasons) => reasons.iter().all(|reason| reason.can_continue()),
+        }
+    }
+
+    /// Stop waiting for every `WakeFlag` of this lock, as the ``Thread`` with this key doesn't wait for them anymore
+    fn cancel(&self, key: TaskKey) {
+        match self {
+            Self::WaitFlag(flag) => flag.cancel(key),
+            Self::Any(reasons) | Self::All(reasons) => {
+                reasons.iter().for_each(|reason| reason.cancel(key))
+            }
+            Self::None | Self::WaitAtomicBool(_) => (),
+        }
+    }
+
+    /// Cancel the parts of the `LockReason::Any`s that weren't released, once the ``Thread`` continue
+    fn cancel_losers(&self, key: TaskKey) {
+        match self {
+            Self::Any(reasons) | Self::All(reasons) => {
+                for reason in reasons {
+                    if reason.can_continue() {
+                        reason.cancel_losers(key);
+                    } else {
+                        reason.cancel(key);
+                    };
+                }
+            }
+            Self::None | Self::WaitAtomicBool(_) | Self::WaitFlag(_) => (),
+        }
+    }
+
+    /// Make the ``Thread`` be put in ready once it may continue. Return ``false`` if this lock can't notify it,
+    /// and should be checked at each pass instead.
+    fn register(&self, key: TaskKey, ready: &ReadyTasks) -> bool {
+        match self {
+            Self::None => {
+                ready.lock().unwrap().insert(key);
+                true
+            }
+            Self::WaitAtomicBool(_) => false,
+            Self::WaitFlag(flag) => {
+                flag.add_waiter(key, ready);
+                true
+            }
+            Self::Any(reasons) => {
+                // every one must be registered, even when one can't notify
+                let mut notify = true;
+                for reason in reasons {
+                    notify &= reason.register(key, ready);
+                }
+                notify
+            }
+            // only wait for the first one that isn't released. It is registered again once released.
+            Self::All(reasons) => match reasons.iter().find(|reason| !reason.can_continue()) {
+                Some(reason) => reason.register(key, ready),
+                None => {
+                    ready.lock().unwrap().insert(key);
+                    true
+                }
+            },
+        }
+    }
+}
+
+/// A value given back to a ``Thread`` when it is resumed, returned by the blocking function that yielded
+#[derive(Clone, Debug, PartialEq, Default)]
+pub enum ResumeValue {
+    #[default]
+    Nil,
+    Boolean(bool),
+    Integer(i64),
+    Number(f64),
+    String(String),
+}
+
+impl IntoLua for ResumeValue {
+    fn into_lua(self, lua: &Lua) -> mlua::Result<Value> {
+        match self {
+            Self::Nil => Ok(Nil),
+            Self::Boolean(value) => value.into_lua(lua),
+            Self::Integer(value) => value.into_lua(lua),
+            Self::Number(value) => value.into_lua(lua),
+            Self::String(value) => value.into_lua(lua),
         }
     }
 }
@@ -36,69 +139,256 @@ impl LockReason {
 /// A struct that should be returned when a ``Thread`` is not finished, but want to interupt it's instruction until another event happend
 pub struct YieldResult {
     lock: LockReason,
+    resume_value: Option<Arc<Mutex<ResumeValue>>>,
 }
 
 impl YieldResult {
     /// Create a new ``YieldResult``. lock is the ``LockReason`` that indicate the external event it wait
     pub fn new(lock: LockReason) -> Self {
-        Self { lock }
+        Self {
+            lock,
+            resume_value: None,
+        }
+    }
+
+    /// Give the content of resume_value back to the ``Thread`` when it is resumed. It should be set before the lock is released.
+    pub fn with_resume_value(mut self, resume_value: Arc<Mutex<ResumeValue>>) -> Self {
+        self.resume_value = Some(resume_value);
+        self
+    }
+
+    /// Combine the `YieldResult`s in one released once any of them is (see `LockReason::Any`). The ``Thread`` is
+    /// resumed with the value of the first one that has one.
+    pub fn any(results: Vec<YieldResult>) -> Self {
+        let resume_value = results
+            .iter()
+            .find_map(|result| result.resume_value.clone());
+        Self {
+            lock: LockReason::Any(results.into_iter().map(|result| result.lock).collect()),
+            resume_value,
+        }
+    }
+
+    /// Combine the `YieldResult`s in one released once all of them are (see `LockReason::All`). The ``Thread`` is
+    /// resumed with the value of the first one that has one.
+    pub fn all(results: Vec<YieldResult>) -> Self {
+        let resume_value = results
+            .iter()
+            .find_map(|result| result.resume_value.clone());
+        Self {
+            lock: LockReason::All(results.into_iter().map(|result| result.lock).collect()),
+            resume_value,
+        }
     }
 }
 
 impl UserData for YieldResult {}
 
+#[derive(Debug)]
+struct Task {
+    /// The ``Thread``, in the registry of lua, where scripts can't reach it
+    thread: RegistryKey,
+    lock: LockReason,
+    priority: i64,
+    /// The value to give to the ``Thread`` when it is resumed, if any
+    resume_value: Option<Arc<Mutex<ResumeValue>>>,
+    name: Option<String>,
+    /// Woken once the ``Thread`` finished or was killed
+    finished: WakeFlag,
+}
+
 #[derive(Default, Debug)]
 struct LuaRunningData {
     next_task_id: u64,
-    task_look_list: HashMap<u64, LockReason>,
+    tasks: BTreeMap<u64, Task>,
+    /// The ``Thread``s that may continue, put here when their lock is released
+    ready: ReadyTasks,
+    /// The ``Thread``s which lock can't notify them, and are thus checked at each pass
+    polled: BTreeSet<u64>,
 }
 
 impl LuaRunningData {
-    fn add_running_thread<'lua>(&mut self, ctx: &Lua, thread: Thread) {
-        let globals = ctx.globals();
-
+    fn add_running_thread(
+        &mut self,
+        ctx: &Lua,
+        thread: Thread,
+        name: Option<String>,
+        priority: Option<i64>,
+    ) -> u64 {
         let created_task_id = self.next_task_id;
         self.next_task_id += 1;
 
-        let running_table = globals.get::<Table>("_yammy_running_coroutine").unwrap();
-        running_table.set(created_task_id, thread).unwrap();
-        globals
-            .set("_yammy_running_coroutine", running_table)
-            .unwrap();
-
-        self.task_look_list
-            .insert(created_task_id, LockReason::None);
+        let task = Task {
+            thread: ctx.create_registry_value(thread).unwrap(),
+            lock: LockReason::None,
+            priority: priority.unwrap_or(0),
+            resume_value: None,
+            name,
+            finished: WakeFlag::new(),
+        };
+        task.lock
+            .register((Reverse(task.priority), created_task_id), &self.ready);
+        self.tasks.insert(created_task_id, task);
+        created_task_id
     }
 
     fn set_running_thread_lock(&mut self, id: u64, reason: LockReason) {
-        self.task_look_list.insert(id, reason);
+        let task = match self.tasks.get_mut(&id) {
+            Some(task) => task,
+            // killed while it was running
+            None => return,
+        };
+        if reason.register((Reverse(task.priority), id), &self.ready) {
+            self.polled.remove(&id);
+        } else {
+            self.polled.insert(id);
+        };
+        task.lock = reason;
+    }
+
+    fn delete_running_thread(&mut self, id: u64) -> Option<Task> {
+        self.polled.remove(&id);
+        let task = self.tasks.remove(&id);
+        if let Some(task) = &task {
+            // what a killed task waited for, like a menu, can be stopped
+            task.lock.cancel((Reverse(task.priority), id));
+            task.finished.wake();
+        };
+        task
+    }
+
+    /// Return the value the ``Thread`` should be resumed with
+    fn take_resume_value(&mut self, id: u64) -> ResumeValue {
+        match self
+            .tasks
+            .get_mut(&id)
+            .and_then(|task| task.resume_value.take())
+        {
+            Some(value) => value.lock().unwrap().clone(),
+            None => ResumeValue::Nil,
+        }
+    }
+
+    /// Return the ``Thread``s that can be resumed, in the order they should be: highest priority first, then in creation order
+    fn list_run_possibility(&mut self) -> Vec<u64> {
+        let mut ready = take(&mut *self.ready.lock().unwrap());
+        for id in &self.polled {
+            if self.tasks[id].lock.can_continue() {
+                ready.insert((Reverse(self.tasks[id].priority), *id));
+            };
+        }
+        let mut to_run = Vec::new();
+        for key in ready {
+            let id = key.1;
+            // a ``Thread`` can be woken while it wait something else, like when it was killed
+            let lock = match self.tasks.get(&id) {
+                Some(task) => task.lock.clone(),
+                None => continue,
+            };
+            if lock.can_continue() {
+                lock.cancel_losers(key);
+                to_run.push(id);
+            } else {
+                // woken by a part of a `LockReason::All`, wait for the rest
+                self.set_running_thread_lock(id, lock);
+            };
+        }
+        to_run
+    }
+}
+
+/// Give access to the ``Thread``s of a `RunningLua` (called tasks) from the lua API, by the id they were given at creation.
+///
+/// The main ``Thread`` of the script has the id 0.
+#[derive(Clone, Debug)]
+pub struct TaskManager {
+    running_data: Arc<Mutex<LuaRunningData>>,
+}
+
+impl TaskManager {
+    /// Start a new task running the function, and return its id. It is first resumed at the next pass of the
+    /// `RunningLua`.
+    pub fn spawn(
+        &self,
+        lua: &Lua,
+        function: Function,
+        name: Option<String>,
+        priority: Option<i64>,
+    ) -> mlua::Result<u64> {
+        let thread = lua.create_thread(function)?;
+        let mut data = self.running_data.lock().unwrap();
+        Ok(data.add_running_thread(lua, thread, name, priority))
+    }
+
+    /// Return ``true`` if the task has neither finished nor been killed
+    pub fn is_running(&self, id: u64) -> bool {
+        self.running_data.lock().unwrap().tasks.contains_key(&id)
+    }
+
+    /// Kill the task, so it is never resumed again. A task killing itself stop at its next yield.
+    ///
+    /// Return ``false`` if the task wasn't running.
+    pub fn kill(&self, lua: &Lua, id: u64) -> bool {
+        let task = self.running_data.lock().unwrap().delete_running_thread(id);
+        match task {
+            Some(task) => {
+                lua.remove_registry_value(task.thread).unwrap();
+                true
+            }
+            None => false,
+        }
     }
 
-    fn delete_running_thread(&mut self, id: u64) {
-        self.task_look_list.remove(&id);
+    /// Return a `WakeFlag` woken once the task finished or was killed. It is already woken if the task isn't running.
+    pub fn finished_flag(&self, id: u64) -> WakeFlag {
+        match self.running_data.lock().unwrap().tasks.get(&id) {
+            Some(task) => task.finished.clone(),
+            None => {
+                let flag = WakeFlag::new();
+                flag.wake();
+                flag
+            }
+        }
     }
 
-    fn list_run_possibility(&self) -> Vec<u64> {
-        self.task_look_list
+    /// Return the id of a running task with this name, if any. The earliest created one is returned if there are many.
+    pub fn find(&self, name: &str) -> Option<u64> {
+        let data = self.running_data.lock().unwrap();
+        data.tasks
             .iter()
-            .filter_map(|(k, v)| if v.can_continue() { Some(*k) } else { None })
-            .collect()
+            .find(|(_, task)| task.name.as_deref() == Some(name))
+            .map(|(id, _)| *id)
     }
 }
 
 /// A running lua script. Multiple ``Thread``s can run in a cooperative parallel mode.
 ///
-/// Inside this environment is avalaible the lua function yammy_fork(function), that will fork the process.
-/// Function can also wait for a long time in a non blocking way (TODO: explain how to do that)
+/// Inside this environment is avalaible the lua function yammy_fork(function, priority), that will fork the process.
+/// priority is optional, and default to 0. It return the id of the new ``Thread`` (see `TaskManager`).
+///
+/// Several waits can be combined with yammy_wait_any(...) and yammy_wait_all(...), then waited with
+/// yammy_wait(condition). Their arguments are the result of the blocking functions called without yielding, like
+/// ``TASK:_Sleep(TimeSec(1))``: ``yammy_wait(yammy_wait_any(TASK:_Sleep(TimeSec(1)), PAD:_WaitButton()))``
+/// wait for a button for at most a second. They can be nested.
+///
+/// ``Thread``s are always resumed in the same order: highest priority first, then in creation order. `RunningLua::execute`
+/// run in multiple passes: each pass resume, in this order, every ``Thread`` that could continue at its start. A ``Thread``
+/// forked or unlocked during a pass (even by a ``Thread`` resumed before it) is resumed in the next pass of the same
+/// call, so ``Thread``s unlocked by the same event resume together, in this order.
+///
+/// Waking is event driven: a locked ``Thread`` isn't checked until its `LockReason` notify it (except for
+/// `LockReason::WaitAtomicBool`), so idle ``Thread``s cost nothing.
 pub struct RunningLua {
     lua: Lua,
     running_data: Arc<Mutex<LuaRunningData>>,
+    error_policy: ErrorPolicy,
 }
 
 impl std::fmt::Debug for RunningLua {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("RunningLua")
             .field("running_data", &self.running_data)
+            .field("error_policy", &self.error_policy)
             .finish()
     }
 }
@@ -108,6 +398,7 @@ impl Default for RunningLua {
         let mut lua = RunningLua {
             lua: Lua::new(),
             running_data: Arc::new(Mutex::new(LuaRunningData::default())),
+            error_policy: ErrorPolicy::default(),
         };
         lua.env_setup();
         lua
@@ -115,95 +406,229 @@ impl Default for RunningLua {
 }
 
 impl RunningLua {
-    pub fn new_from_script(script: &str) -> RunningLua {
+    pub fn new_from_script<S: AsRef<[u8]> + ?Sized>(script: &S) -> Result<RunningLua, ScriptError> {
         let mut new = Self::default();
-        new.load_script(script);
-        new
+        new.load_script(script)?;
+        Ok(new)
     }
 
-    pub fn load_script(&mut self, script: &str) {
-        let main_thread = self
+    /// Load the script, and start it as a new ``Thread``. Return an error if the script can't be compiled.
+    ///
+    /// The script can be either lua source code or lua 5.1 bytecode (see `ScriptCode` to load bytecode from the 3DS).
+    pub fn load_script<S: AsRef<[u8]> + ?Sized>(&mut self, script: &S) -> Result<(), ScriptError> {
+        let main_function = self
             .lua
-            .create_thread(self.lua.load(script).into_function().unwrap())
-            .unwrap();
+            .load(script.as_ref())
+            .set_name(format!("={}", SCRIPT_CHUNK_NAME))
+            .into_function()
+            .map_err(|err| ScriptError::from_lua_error(&err, None))?;
+        let main_thread = self.lua.create_thread(main_function).unwrap();
         {
             let mut data = self.running_data.lock().unwrap();
-            data.add_running_thread(&self.lua, main_thread);
+            data.add_running_thread(&self.lua, main_thread, None, None);
         }
+        Ok(())
+    }
+
+    /// Choose what happen when a ``Thread`` fail. Default to `ErrorPolicy::AbortScene`.
+    pub fn set_error_policy(&mut self, error_policy: ErrorPolicy) {
+        self.error_policy = error_policy;
     }
 
     fn env_setup(&mut self) {
         let globals = self.lua.globals();
-        globals
-            .set("_yammy_running_coroutine", self.lua.create_table().unwrap())
-            .unwrap();
 
-        let running_data_cloned = self.running_data.clone();
+        let tasks = self.tasks();
         let yammy_fork = self
             .lua
-            .create_function(move |ctx, function: Function| {
-                let thread = ctx.create_thread(function).unwrap();
-                {
-                    let mut data = running_data_cloned.lock().unwrap();
-                    data.add_running_thread(&ctx, thread);
-                }
-                Ok(())
+            .create_function(move |ctx, (function, priority): (Function, Option<i64>)| {
+                tasks.spawn(ctx, function, None, priority)
             })
             .unwrap();
         globals.set("yammy_fork", yammy_fork).unwrap();
+
+        let yammy_wait_any = self
+            .lua
+            .create_function(|_, results: Variadic<YieldResult>| {
+                Ok(YieldResult::any(results.into_iter().collect()))
+            })
+            .unwrap();
+        globals.set("yammy_wait_any", yammy_wait_any).unwrap();
+        let yammy_wait_all = self
+            .lua
+            .create_function(|_, results: Variadic<YieldResult>| {
+                Ok(YieldResult::all(results.into_iter().collect()))
+            })
+            .unwrap();
+        globals.set("yammy_wait_all", yammy_wait_all).unwrap();
+        let yammy_wait: Function = self
+            .lua
+            .load(
+                "function(condition)
+                    return coroutine.yield(condition)
+                end",
+            )
+            .eval()
+            .unwrap();
+        globals.set("yammy_wait", yammy_wait).unwrap();
     }
 
-    fn step(&mut self) -> bool {
+    fn step(&mut self) -> Result<bool, ScriptError> {
         let to_run;
         {
-            let data = self.running_data.lock().unwrap();
+            let mut data = self.running_data.lock().unwrap();
             to_run = data.list_run_possibility();
             if to_run.is_empty() {
-                return false;
+                return Ok(false);
             }
         }
+        let mut first_error = None;
         for to_run_id in to_run {
-            self.continue_running_thread(to_run_id);
+            if !self
+                .running_data
+                .lock()
+                .unwrap()
+                .tasks
+                .contains_key(&to_run_id)
+            {
+                // killed by the failure of a previous thread
+                continue;
+            };
+            if let Err(err) = self.continue_running_thread(to_run_id) {
+                match self.error_policy {
+                    ErrorPolicy::KillTask => self.kill_thread(to_run_id),
+                    ErrorPolicy::AbortScene => {
+                        self.kill_all_threads();
+                        return Err(err);
+                    }
+                };
+                keep_first_error(&mut first_error, err);
+            };
+        }
+        match first_error {
+            Some(err) => Err(err),
+            None => Ok(true),
         }
-        true
     }
 
-    fn continue_running_thread(&mut self, id: u64) {
-        let globals = self.lua.globals();
-        let running_coroutine_table = globals.get::<Table>("_yammy_running_coroutine").unwrap();
-        let thread = running_coroutine_table.get::<Thread>(id).unwrap();
-        let result = thread.resume::<Option<YieldResult>>(()).unwrap();
+    fn continue_running_thread(&mut self, id: u64) -> Result<(), ScriptError> {
+        let (thread, resume_value) = {
+            let mut data = self.running_data.lock().unwrap();
+            let thread = match data.tasks.get(&id) {
+                Some(task) => self.lua.registry_value::<Thread>(&task.thread).unwrap(),
+                None => return Ok(()),
+            };
+            (thread, data.take_resume_value(id))
+        };
+        let result = thread
+            .resume::<Value>(resume_value)
+            .map_err(|err| ScriptError::from_lua_error(&err, Some(id)))?;
         // check if the thread is finished
-        let mut data = self.running_data.lock().unwrap();
         match thread.status() {
-            ThreadStatus::Resumable => match result {
-                None => panic!(),
-                Some(value) => {
-                    data.set_running_thread_lock(id, value.lock);
+            ThreadStatus::Resumable => {
+                // The function that yielded managed the state of this
+                let yield_result = match &result {
+                    Value::UserData(userdata) => userdata
+                        .borrow::<YieldResult>()
+                        .map(|value| value.clone())
+                        .ok(),
+                    _ => None,
+                };
+                match yield_result {
+                    Some(value) => {
+                        let mut data = self.running_data.lock().unwrap();
+                        if let Some(task) = data.tasks.get_mut(&id) {
+                            task.resume_value = value.resume_value;
+                        };
+                        data.set_running_thread_lock(id, value.lock);
+                    }
+                    None => {
+                        return Err(ScriptError::new(
+                            format!(
+                                "coroutine.yield was called with a {} instead of the result of a blocking function",
+                                result.type_name()
+                            ),
+                            Some(id),
+                        ))
+                    }
                 }
-            }, // The function that yielded managed the state of this
+            }
             ThreadStatus::Running => (),
-            ThreadStatus::Finished => {
-                data.delete_running_thread(id);
-                running_coroutine_table.set(id, Nil).unwrap();
-                globals
-                    .set("_yammy_running_coroutine", running_coroutine_table)
-                    .unwrap();
+            ThreadStatus::Finished => self.kill_thread(id),
+            ThreadStatus::Error => {
+                return Err(ScriptError::new(
+                    "the thread is in an error state".into(),
+                    Some(id),
+                ))
+            }
+        };
+        Ok(())
+    }
+
+    /// Remove a ``Thread``, so it will never be resumed again
+    fn kill_thread(&mut self, id: u64) {
+        self.tasks().kill(&self.lua, id);
+    }
+
+    fn kill_all_threads(&mut self) {
+        let ids: Vec<u64> = {
+            let data = self.running_data.lock().unwrap();
+            data.tasks.keys().copied().collect()
+        };
+        for id in ids {
+            self.kill_thread(id);
+        }
+    }
+
+    /// Run every ``Thread`` until they are all locked or finished.
+    ///
+    /// Return the first error raised by a ``Thread``. Depending on the `ErrorPolicy`, either the failed ``Thread`` or all of them are killed.
+    pub fn execute(&mut self) -> Result<(), ScriptError> {
+        let mut first_error = None;
+        loop {
+            match self.step() {
+                Ok(true) => (),
+                Ok(false) => break,
+                Err(err) => {
+                    if self.error_policy == ErrorPolicy::AbortScene {
+                        return Err(err);
+                    };
+                    keep_first_error(&mut first_error, err);
+                }
             }
-            ThreadStatus::Error => panic!(),
+        }
+        match first_error {
+            Some(err) => Err(err),
+            None => Ok(()),
         }
     }
 
-    pub fn execute(&mut self) {
-        while self.step() {}
+    /// Return ``true`` if there is no more ``Thread`` to run, locked or not
+    pub fn is_finished(&self) -> bool {
+        self.running_data.lock().unwrap().tasks.is_empty()
     }
 
     pub fn lua(&self) -> &Lua {
         &self.lua
     }
+
+    pub fn tasks(&self) -> TaskManager {
+        TaskManager {
+            running_data: self.running_data.clone(),
+        }
+    }
+}
+
+/// Keep the first error to return it, and log the following one, as only one can be returned
+fn keep_first_error(first_error: &mut Option<ScriptError>, err: ScriptError) {
+    if first_error.is_some() {
+        error!("{}", err);
+    } else {
+        *first_error = Some(err);
+    }
 }
 
-pub fn add_locking_function<'lua>(
+pub fn add_locking_function(
     ctx: &Lua,
     front_user_function_name: &str,
     internal_function_name: &str,
@@ -214,8 +639,8 @@ pub fn add_locking_function<'lua>(
         .set(internal_function_name, internal_function)
         .unwrap();
     let front_function = ctx
-        .load(&format!(
-            "local temp_result = {}()\ncoroutine.yield(temp_result)",
+        .load(format!(
+            "local temp_result = {}()\nreturn coroutine.yield(temp_result)",
             internal_function_name
         ))
         .into_function()
@@ -229,8 +654,8 @@ mod test {
     #[test]
     fn test_running_lua() {
         use crate::RunningLua;
-        let mut runninglua = RunningLua::new_from_script("a = 3");
-        runninglua.execute();
+        let mut runninglua = RunningLua::new_from_script("a = 3").unwrap();
+        runninglua.execute().unwrap();
         let globals = runninglua.lua().globals();
         assert_eq!(globals.get::<u64>("a").unwrap(), 3);
     }
@@ -260,8 +685,9 @@ mod test {
             _yammy_test_lock_internal,
         );
 
-        runninglua.load_script(
-            "
+        runninglua
+            .load_script(
+                "
             a = 0
             function testa()
                 a = a + 1
@@ -271,10 +697,11 @@ mod test {
             yammy_fork(testa)
             yammy_test_lock()
             a = a + 1",
-        );
+            )
+            .unwrap();
 
         for _ in 0..10 {
-            runninglua.execute();
+            runninglua.execute().unwrap();
         }
 
         {
@@ -285,10 +712,358 @@ mod test {
         pass_value.store(true, Relaxed);
 
         for _ in 0..10 {
-            runninglua.execute();
+            runninglua.execute().unwrap();
         }
 
         let globals = runninglua.lua().globals();
         assert_eq!(globals.get::<u64>("a").unwrap(), 3);
     }
+
+    #[test]
+    fn test_resume_value() {
+        use crate::{add_locking_function, LockReason, ResumeValue, RunningLua, YieldResult};
+        use std::sync::{
+            atomic::{AtomicBool, Ordering::Relaxed},
+            Arc, Mutex,
+        };
+        let mut runninglua = RunningLua::default();
+        let pass_value = Arc::new(AtomicBool::new(false));
+        let resume_value = Arc::new(Mutex::new(ResumeValue::Nil));
+        let (pass_value_cloned, resume_value_cloned) = (pass_value.clone(), resume_value.clone());
+        let _yammy_test_ask_internal = runninglua
+            .lua()
+            .create_function(move |_, ()| {
+                Ok(
+                    YieldResult::new(LockReason::WaitAtomicBool(pass_value_cloned.clone()))
+                        .with_resume_value(resume_value_cloned.clone()),
+                )
+            })
+            .unwrap();
+        add_locking_function(
+            runninglua.lua(),
+            "yammy_test_ask",
+            "_yammy_test_ask_internal",
+            _yammy_test_ask_internal,
+        );
+        runninglua.load_script("a = yammy_test_ask()").unwrap();
+        runninglua.execute().unwrap();
+        *resume_value.lock().unwrap() = ResumeValue::String("answer".into());
+        pass_value.store(true, Relaxed);
+        runninglua.execute().unwrap();
+        assert!(runninglua.is_finished());
+        let globals = runninglua.lua().globals();
+        assert_eq!(globals.get::<String>("a").unwrap(), "answer");
+    }
+
+    #[test]
+    fn test_thread_order() {
+        use crate::{add_locking_function, LockReason, RunningLua, YieldResult};
+        use std::sync::{
+            atomic::{AtomicBool, Ordering::Relaxed},
+            Arc,
+        };
+        let mut runninglua = RunningLua::default();
+        let event = Arc::new(AtomicBool::new(false));
+        let event_cloned = event.clone();
+        let _yammy_test_wait_internal = runninglua
+            .lua()
+            .create_function(move |_, ()| {
+                Ok(YieldResult::new(LockReason::WaitAtomicBool(
+                    event_cloned.clone(),
+                )))
+            })
+            .unwrap();
+        add_locking_function(
+            runninglua.lua(),
+            "yammy_test_wait",
+            "_yammy_test_wait_internal",
+            _yammy_test_wait_internal,
+        );
+        runninglua
+            .load_script(
+                "
+            order = \"\"
+            for i = 1, 20 do
+                yammy_fork(function()
+                    order = order .. i .. \",\"
+                    yammy_test_wait()
+                    order = order .. i .. \",\"
+                end, i % 3 == 0 and 1 or nil)
+            end
+            yammy_fork(function()
+                order = order .. \"late,\"
+            end, -1)
+            yammy_test_wait()
+            order = order .. \"main\"",
+            )
+            .unwrap();
+        runninglua.execute().unwrap();
+        // the threads with the highest priority first, then in creation order
+        let first_pass = "3,6,9,12,15,18,1,2,4,5,7,8,10,11,13,14,16,17,19,20,late,";
+        assert_eq!(
+            runninglua.lua().globals().get::<String>("order").unwrap(),
+            first_pass
+        );
+        // every thread waiting for the same event resume in the same order, the main one being the first created
+        event.store(true, Relaxed);
+        runninglua.execute().unwrap();
+        assert_eq!(
+            runninglua.lua().globals().get::<String>("order").unwrap(),
+            format!(
+                "{}3,6,9,12,15,18,main1,2,4,5,7,8,10,11,13,14,16,17,19,20,",
+                first_pass
+            )
+        );
+    }
+
+    #[test]
+    fn test_wake_flag_lock() {
+        use crate::{add_locking_function, LockReason, RunningLua, WakeFlag, YieldResult};
+        use std::sync::{Arc, Mutex};
+        let mut runninglua = RunningLua::default();
+        let flags = Arc::new(Mutex::new(Vec::new()));
+        let flags_cloned = flags.clone();
+        let _yammy_test_wait_internal = runninglua
+            .lua()
+            .create_function(move |_, ()| {
+                let flag = WakeFlag::new();
+                flags_cloned.lock().unwrap().push(flag.clone());
+                Ok(YieldResult::new(LockReason::new_flag(flag)))
+            })
+            .unwrap();
+        add_locking_function(
+            runninglua.lua(),
+            "yammy_test_wait",
+            "_yammy_test_wait_internal",
+            _yammy_test_wait_internal,
+        );
+        runninglua
+            .load_script(
+                "
+            done = \"\"
+            for i = 1, 3 do
+                yammy_fork(function()
+                    yammy_test_wait()
+                    done = done .. i
+                end)
+            end",
+            )
+            .unwrap();
+        runninglua.execute().unwrap();
+        let flags = flags.lock().unwrap().clone();
+        assert_eq!(flags.len(), 3);
+        // only the thread waiting for the woken flag is resumed
+        flags[1].wake();
+        runninglua.execute().unwrap();
+        assert_eq!(
+            runninglua.lua().globals().get::<String>("done").unwrap(),
+            "2"
+        );
+        flags[2].wake();
+        flags[0].wake();
+        runninglua.execute().unwrap();
+        assert_eq!(
+            runninglua.lua().globals().get::<String>("done").unwrap(),
+            "213"
+        );
+        assert!(runninglua.is_finished());
+        // the threads are not reachable from the scripts
+        assert!(runninglua
+            .lua()
+            .globals()
+            .get::<mlua::Value>("_yammy_running_coroutine")
+            .unwrap()
+            .is_nil());
+    }
+
+    #[test]
+    fn test_composite_lock() {
+        use crate::{LockReason, RunningLua, WakeFlag, YieldResult};
+        let mut runninglua = RunningLua::default();
+        let flags: Vec<WakeFlag> = (0..4).map(|_| WakeFlag::new()).collect();
+        let flags_cloned = flags.clone();
+        let yammy_test_flag = runninglua
+            .lua()
+            .create_function(move |_, index: usize| {
+                Ok(YieldResult::new(LockReason::new_flag(
+                    flags_cloned[index].clone(),
+                )))
+            })
+            .unwrap();
+        runninglua
+            .lua()
+            .globals()
+            .set("yammy_test_flag", yammy_test_flag)
+            .unwrap();
+        runninglua
+            .load_script(
+                "
+            step = 0
+            yammy_wait(yammy_wait_all(yammy_test_flag(0), yammy_test_flag(1)))
+            step = 1
+            yammy_wait(yammy_wait_any(
+                yammy_test_flag(2),
+                yammy_wait_all(yammy_test_flag(3), yammy_test_flag(0))
+            ))
+            step = 2",
+            )
+            .unwrap();
+        let step = |runninglua: &mut RunningLua| {
+            runninglua.execute().unwrap();
+            runninglua.lua().globals().get::<u64>("step").unwrap()
+        };
+        assert_eq!(step(&mut runninglua), 0);
+        flags[1].wake();
+        assert_eq!(step(&mut runninglua), 0);
+        flags[0].wake();
+        assert_eq!(step(&mut runninglua), 1);
+        // flag 0 is already woken, so waking flag 3 release the inner all
+        flags[3].wake();
+        assert_eq!(step(&mut runninglua), 2);
+        assert!(runninglua.is_finished());
+    }
+
+    #[test]
+    fn test_signal() {
+        use crate::gamedata::{Scene, Update};
+        use crate::luaapi::initialize_lua_environment;
+        use crate::RunningLua;
+        use std::sync::{Arc, Mutex};
+        let mut runninglua = RunningLua::default();
+        let scene = Arc::new(Mutex::new(Scene::default()));
+        initialize_lua_environment(&runninglua, &scene);
+
+        runninglua
+            .load_script(
+                "
+            a = 0
+            function partner()
+                a = a + 1
+                TASK:WaitSignal(\"line_finished\")
+                a = a + 1
+            end
+            yammy_fork(partner)
+            yammy_fork(partner)
+            TASK:WaitSignal(\"go\")
+            TASK:Signal(\"line_finished\")
+            a = a + 10
+            TASK:WaitSignal(\"line_finished\")
+            a = a + 10",
+            )
+            .unwrap();
+
+        for _ in 0..10 {
+            runninglua.execute().unwrap();
+        }
+
+        {
+            let globals = runninglua.lua().globals();
+            assert_eq!(globals.get::<u64>("a").unwrap(), 2);
+        }
+
+        scene
+            .lock()
+            .unwrap()
+            .update(Update::RaiseSignal("go".into()));
+
+        for _ in 0..10 {
+            runninglua.execute().unwrap();
+        }
+
+        // both waiting tasks are released, and the raised signal doesn't block anymore
+        let globals = runninglua.lua().globals();
+        assert_eq!(globals.get::<u64>("a").unwrap(), 24);
+        assert!(runninglua.is_finished());
+    }
+
+    #[test]
+    fn test_shared_wait_task() {
+        use crate::gamedata::{Scene, Time, Update};
+        use crate::luaapi::initialize_lua_environment;
+        use crate::RunningLua;
+        use std::sync::{Arc, Mutex};
+        let mut runninglua = RunningLua::default();
+        let scene = Arc::new(Mutex::new(Scene::default()));
+        initialize_lua_environment(&runninglua, &scene);
+
+        // the waiters giving up on the task, or killed, don't stop the others waiting for it
+        runninglua
+            .load_script(
+                "
+            local h = TASK:Regist(function() TASK:Sleep(TimeSec(2)) end)
+            yammy_fork(function()
+                yammy_wait(yammy_wait_any(TASK:_WaitTask(h), TASK:_Sleep(TimeSec(0.5))))
+                timed_out = true
+            end)
+            local killed = TASK:Regist(function() TASK:WaitTask(h) end)
+            yammy_fork(function()
+                TASK:Sleep(TimeSec(1))
+                TASK:Kill(killed)
+            end)
+            TASK:WaitTask(h)
+            finished = true",
+            )
+            .unwrap();
+
+        for _ in 0..30 {
+            runninglua.execute().unwrap();
+            scene
+                .lock()
+                .unwrap()
+                .update(Update::TimeSpent(Time::new(0.1)));
+        }
+
+        let globals = runninglua.lua().globals();
+        assert!(globals.get::<bool>("timed_out").unwrap());
+        assert!(globals.get::<bool>("finished").unwrap());
+        assert!(runninglua.is_finished());
+    }
+
+    #[test]
+    fn test_script_error() {
+        use crate::RunningLua;
+        let err = RunningLua::new_from_script("a = = 3").unwrap_err();
+        assert_eq!(err.task_id, None);
+        assert_eq!(err.line, Some(1));
+
+        let mut runninglua = RunningLua::new_from_script(
+            "
+            yammy_fork(function()
+                local a = 1
+                error(\"boom\")
+            end)",
+        )
+        .unwrap();
+        let err = runninglua.execute().unwrap_err();
+        assert_eq!(err.task_id, Some(1));
+        assert_eq!(err.line, Some(4));
+        assert!(err.message.contains("boom"));
+        assert!(runninglua.is_finished());
+
+        let mut runninglua = RunningLua::new_from_script("coroutine.yield(3)").unwrap();
+        let err = runninglua.execute().unwrap_err();
+        assert_eq!(err.task_id, Some(0));
+        assert!(runninglua.is_finished());
+    }
+
+    #[test]
+    fn test_error_policy() {
+        use crate::{ErrorPolicy, RunningLua};
+        let script = "
+            a = 0
+            yammy_fork(function()
+                a = a + 1
+            end)
+            error(\"boom\")";
+        let mut runninglua = RunningLua::new_from_script(script).unwrap();
+        runninglua.set_error_policy(ErrorPolicy::KillTask);
+        assert!(runninglua.execute().is_err());
+        assert_eq!(runninglua.lua().globals().get::<u64>("a").unwrap(), 1);
+
+        let mut runninglua = RunningLua::new_from_script(script).unwrap();
+        runninglua.set_error_policy(ErrorPolicy::AbortScene);
+        assert!(runninglua.execute().is_err());
+        assert_eq!(runninglua.lua().globals().get::<u64>("a").unwrap(), 0);
+        assert!(runninglua.is_finished());
+    }
 }
@@ -0,0 +1,20 @@
+use mlua::{AnyUserData, MetaMethod, Table, UserDataMethods, Value};
+
+/// The name of the table containing the lua wrapper of every blocking method, indexed by their name. It's stored in
+/// the lua registry, so the scripts can't replace it.
+pub const BLOCKING_METHOD_TABLE: &str = "_yammy_blocking_method";
+
+/// Make the blocking methods callable directly on this userdata, like ``TASK:Sleep(TimeSec(1))``.
+///
+/// A blocking method is implemented in rust as ``_Name``, returning a `YieldResult`. As a rust function
+/// can't yield, ``Name`` is looked up in `BLOCKING_METHOD_TABLE`, that contain a lua function calling
+/// ``_Name`` then yielding its result. Normal methods are found before this lookup happen.
+pub fn add_blocking_method_lookup<T, M: UserDataMethods<T>>(methods: &mut M) {
+    methods.add_meta_function(
+        MetaMethod::Index,
+        |lua, (_, name): (AnyUserData, String)| {
+            let blocking_methods = lua.named_registry_value::<Table>(BLOCKING_METHOD_TABLE)?;
+            blocking_methods.get::<Value>(name)
+        },
+    );
+}
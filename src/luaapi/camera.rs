@@ -0,0 +1,88 @@
+use crate::gamedata::{Easing, Lock, Scene, Time, Update};
+use crate::luaapi::{add_blocking_method_lookup, read_position};
+use crate::{LockReason, WakeFlag, YieldResult};
+use mlua::{AnyUserData, UserData, UserDataMethods};
+use std::sync::{Arc, Mutex};
+
+pub struct CAMERA {
+    scene: Arc<Mutex<Scene>>,
+}
+
+impl CAMERA {
+    pub fn new(scene: Arc<Mutex<Scene>>) -> Self {
+        Self { scene }
+    }
+}
+
+impl UserData for CAMERA {
+    fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
+        add_blocking_method_lookup(methods);
+        // look at the position (a Vector or a Vector2) immediately
+        methods.add_method("SetEye", |_, this, position: AnyUserData| {
+            let mut scene = this.scene.lock().unwrap();
+            scene.update(Update::MoveCamera(
+                read_position(position)?,
+                Time::default(),
+                Easing::Linear,
+            ));
+            Ok(())
+        });
+        // move the point looked at in the given time, with an optional value of the EASE table
+        methods.add_method(
+            "MoveTo",
+            |_, this, (position, duration, easing): (AnyUserData, Time, Option<Easing>)| {
+                let mut scene = this.scene.lock().unwrap();
+                scene.update(Update::MoveCamera(
+                    read_position(position)?,
+                    duration,
+                    easing.unwrap_or_default(),
+                ));
+                Ok(())
+            },
+        );
+        // keep the character (by it's name) at the center of the screen, until the camera is moved
+        methods.add_method("FollowChara", |_, this, name: String| {
+            let mut scene = this.scene.lock().unwrap();
+            scene.update(Update::FollowChara(Some(name)));
+            Ok(())
+        });
+        methods.add_method("StopFollow", |_, this, (): ()| {
+            let mut scene = this.scene.lock().unwrap();
+            scene.update(Update::FollowChara(None));
+            Ok(())
+        });
+        // 1 is the normal size, 2 twice larger. Immediate without time.
+        methods.add_method(
+            "SetZoom",
+            |_, this, (zoom, duration, easing): (f64, Option<Time>, Option<Easing>)| {
+                let mut scene = this.scene.lock().unwrap();
+                scene.update(Update::ZoomCamera(
+                    zoom,
+                    duration.unwrap_or_default(),
+                    easing.unwrap_or_default(),
+                ));
+                Ok(())
+            },
+        );
+        // rotate the view clockwise, in degree. Immediate without time.
+        methods.add_method(
+            "SetRotation",
+            |_, this, (angle, duration, easing): (f64, Option<Time>, Option<Easing>)| {
+                let mut scene = this.scene.lock().unwrap();
+                scene.update(Update::RotateCamera(
+                    angle.to_radians(),
+                    duration.unwrap_or_default(),
+                    easing.unwrap_or_default(),
+                ));
+                Ok(())
+            },
+        );
+        // wait until the camera stopped moving, zooming and rotating
+        methods.add_method("_WaitMove", |_, this, (): ()| {
+            let mut scene = this.scene.lock().unwrap();
+            let flag = WakeFlag::new();
+            scene.update(Update::AddLock(Lock::WaitCamera(flag.clone())));
+            Ok(YieldResult::new(LockReason::new_flag(flag)))
+        });
+    }
+}
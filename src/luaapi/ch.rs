@@ -1,8 +1,12 @@
-use crate::gamedata::{Lock, Scene, Speed, Update, Vec2_f64, Vec3_f64};
+use crate::gamedata::{
+    Easing, Lock, Motion, MoveKind, Scene, Speed, Time, Update, Vec2_f64, Vec3_f64,
+};
+use crate::luaapi::add_blocking_method_lookup;
 use crate::LockReason;
+use crate::WakeFlag;
 use crate::YieldResult;
-use mlua::{UserData, UserDataMethods};
-use std::sync::{atomic::AtomicBool, Arc, Mutex};
+use mlua::{AnyUserData, UserData, UserDataMethods};
+use std::sync::{Arc, Mutex, MutexGuard};
 
 pub struct CH {
     scene: Arc<Mutex<Scene>>,
@@ -13,28 +17,271 @@ impl CH {
     pub fn new(scene: Arc<Mutex<Scene>>, id: String) -> CH {
         CH { scene, id }
     }
+
+    /// Return the position of a character (by it's name) on the ground
+    fn chara_position(scene: &Scene, id: &str) -> mlua::Result<Vec2_f64> {
+        match scene.charas.get(&scene.resolve_chara_id(id)) {
+            Some(chara) => Ok(chara.position.to_vec2()),
+            None => Err(mlua::Error::external(format!(
+                "the character {:?} isn't loaded",
+                id
+            ))),
+        }
+    }
+
+    /// Lock the scene, failing if the character isn't loaded, or was removed
+    fn lock_scene(&self) -> mlua::Result<MutexGuard<'_, Scene>> {
+        let scene = self.scene.lock().unwrap();
+        Self::chara_position(&scene, &self.id)?;
+        Ok(scene)
+    }
+
+    /// Turn to the angle (in radians), immediately if duration is ``None``
+    fn turn(&self, scene: &mut Scene, angle: f64, duration: Option<Time>) {
+        scene.update(match duration {
+            Some(duration) => Update::TurnTo(self.id.clone(), angle, duration),
+            None => Update::SetAngle(self.id.clone(), angle),
+        });
+    }
+
+    /// Wait until the character landed, or reached the height it was set to
+    fn wait_height(&self) -> mlua::Result<YieldResult> {
+        let mut scene = self.lock_scene()?;
+        let flag = WakeFlag::new();
+        scene.update(Update::AddLock(Lock::WaitHeight(
+            flag.clone(),
+            self.id.clone(),
+        )));
+        Ok(YieldResult::new(LockReason::new_flag(flag)))
+    }
+
+    /// Turn to face the position, immediately if duration is ``None``
+    fn turn_toward(
+        &self,
+        scene: &mut Scene,
+        target: Vec2_f64,
+        duration: Option<Time>,
+    ) -> mlua::Result<()> {
+        let position = Self::chara_position(scene, &self.id)?;
+        if target != position {
+            self.turn(scene, (target - position).angle(), duration);
+        };
+        Ok(())
+    }
+}
+
+/// Read a name of the ``MOVE`` table, defaulting to walking
+fn read_move_kind(name: Option<String>) -> mlua::Result<MoveKind> {
+    let name = match name {
+        Some(name) => name,
+        None => return Ok(MoveKind::Walk),
+    };
+    MoveKind::ALL
+        .iter()
+        .find(|(kind_name, _)| *kind_name == name)
+        .map(|(_, kind)| *kind)
+        .ok_or_else(|| mlua::Error::external(format!("unknown way of moving {:?}", name)))
+}
+
+/// Read a position given either as a ``Vector`` or a ``Vector2``
+pub(crate) fn read_position(position: AnyUserData) -> mlua::Result<Vec2_f64> {
+    match position.borrow::<Vec3_f64>() {
+        Ok(position) => Ok(position.to_vec2()),
+        Err(_) => position.borrow::<Vec2_f64>().map(|position| *position),
+    }
 }
 
 impl UserData for CH {
     fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
+        add_blocking_method_lookup(methods);
         methods.add_method("SetPosition", |_, this, position: Vec3_f64| {
-            let mut scene = this.scene.lock().unwrap();
+            let mut scene = this.lock_scene()?;
             scene.update(Update::SetPosition(this.id.clone(), position));
             Ok(())
         });
-        methods.add_method("WalkTo", |_, this, (position, speed): (Vec2_f64, Speed)| {
-            let mut scene = this.scene.lock().unwrap();
-            scene.update(Update::WalkTo(this.id.clone(), position, speed));
+        // the moves can take a value of the EASE table, to change the speed along the way
+        methods.add_method(
+            "WalkTo",
+            |_, this, (position, speed, easing): (Vec2_f64, Speed, Option<Easing>)| {
+                let mut scene = this.lock_scene()?;
+                scene.update(match easing {
+                    None => Update::WalkTo(this.id.clone(), position, speed),
+                    Some(easing) => Update::MoveAlong(
+                        this.id.clone(),
+                        vec![position],
+                        speed,
+                        MoveKind::Walk,
+                        easing,
+                    ),
+                });
+                Ok(())
+            },
+        );
+        methods.add_method(
+            "RunTo",
+            |_, this, (position, speed, easing): (AnyUserData, Speed, Option<Easing>)| {
+                let mut scene = this.lock_scene()?;
+                scene.update(Update::MoveAlong(
+                    this.id.clone(),
+                    vec![read_position(position)?],
+                    speed,
+                    MoveKind::Run,
+                    easing.unwrap_or_default(),
+                ));
+                Ok(())
+            },
+        );
+        // move without animation, nor changing the direction
+        methods.add_method(
+            "SlideTo",
+            |_, this, (position, speed, easing): (AnyUserData, Speed, Option<Easing>)| {
+                let mut scene = this.lock_scene()?;
+                scene.update(Update::MoveAlong(
+                    this.id.clone(),
+                    vec![read_position(position)?],
+                    speed,
+                    MoveKind::Slide,
+                    easing.unwrap_or_default(),
+                ));
+                Ok(())
+            },
+        );
+        // walk by offset, from the current position
+        methods.add_method(
+            "MoveToRelative",
+            |_, this, (offset, speed, easing): (AnyUserData, Speed, Option<Easing>)| {
+                let mut scene = this.lock_scene()?;
+                let destination = Self::chara_position(&scene, &this.id)? + read_position(offset)?;
+                scene.update(Update::MoveAlong(
+                    this.id.clone(),
+                    vec![destination],
+                    speed,
+                    MoveKind::Walk,
+                    easing.unwrap_or_default(),
+                ));
+                Ok(())
+            },
+        );
+        // go through every point of the table, in order, then stop. kind is a value of the MOVE table, WALK by default.
+        methods.add_method(
+            "MovePath",
+            |_,
+             this,
+             (path, speed, kind, easing): (
+                Vec<AnyUserData>,
+                Speed,
+                Option<String>,
+                Option<Easing>,
+            )| {
+                let path = path
+                    .into_iter()
+                    .map(read_position)
+                    .collect::<mlua::Result<Vec<Vec2_f64>>>()?;
+                let kind = read_move_kind(kind)?;
+                let mut scene = this.lock_scene()?;
+                scene.update(Update::MoveAlong(
+                    this.id.clone(),
+                    path,
+                    speed,
+                    kind,
+                    easing.unwrap_or_default(),
+                ));
+                Ok(())
+            },
+        );
+        // face the direction (in degree, like the DIR constants) immediately
+        methods.add_method("SetDir", |_, this, direction: f64| {
+            let mut scene = this.lock_scene()?;
+            this.turn(&mut scene, direction.to_radians(), None);
             Ok(())
         });
+        // turn to face the direction (in degree) in the given time
+        methods.add_method("TurnTo", |_, this, (direction, duration): (f64, Time)| {
+            let mut scene = this.lock_scene()?;
+            this.turn(&mut scene, direction.to_radians(), Some(duration));
+            Ok(())
+        });
+        // face the position, either immediately or in the given time
+        methods.add_method(
+            "DirTo",
+            |_, this, (position, duration): (AnyUserData, Option<Time>)| {
+                let mut scene = this.lock_scene()?;
+                this.turn_toward(&mut scene, read_position(position)?, duration)
+            },
+        );
+        // face another character (by it's name), either immediately or in the given time
+        methods.add_method(
+            "TurnToChara",
+            |_, this, (other, duration): (String, Option<Time>)| {
+                let mut scene = this.lock_scene()?;
+                let target = Self::chara_position(&scene, &other)?;
+                this.turn_toward(&mut scene, target, duration)
+            },
+        );
+        // jump up to the height above the current one, landing after the duration
+        methods.add_method("Jump", |_, this, (height, duration): (f64, Time)| {
+            let mut scene = this.lock_scene()?;
+            scene.update(Update::Jump(this.id.clone(), height, duration));
+            Ok(())
+        });
+        // change the height (like to fall), either immediately or in the given time
+        methods.add_method(
+            "SetHeight",
+            |_, this, (height, duration, easing): (f64, Option<Time>, Option<Easing>)| {
+                let mut scene = this.lock_scene()?;
+                scene.update(Update::SetHeight(
+                    this.id.clone(),
+                    height,
+                    duration.unwrap_or_default(),
+                    easing.unwrap_or_default(),
+                ));
+                Ok(())
+            },
+        );
+        // play the motion (like MOTION.TALK) in loop
+        methods.add_method("SetMotion", |_, this, name: String| {
+            let mut scene = this.lock_scene()?;
+            scene.update(Update::SetMotion(this.id.clone(), Motion::new(&name, true)));
+            Ok(())
+        });
+        // play the motion once, then come back to the idle one
+        methods.add_method("PlayMotion", |_, this, name: String| {
+            let mut scene = this.lock_scene()?;
+            scene.update(Update::SetMotion(
+                this.id.clone(),
+                Motion::new(&name, false),
+            ));
+            Ok(())
+        });
+        // wait until the motion played once finished
+        methods.add_method("_WaitMotion", |_, this, (): ()| {
+            let mut scene = this.lock_scene()?;
+            let flag = WakeFlag::new();
+            scene.update(Update::AddLock(Lock::WaitMotion(
+                flag.clone(),
+                this.id.clone(),
+            )));
+            Ok(YieldResult::new(LockReason::new_flag(flag)))
+        });
+        methods.add_method("_WaitTurn", |_, this, (): ()| {
+            let mut scene = this.lock_scene()?;
+            let flag = WakeFlag::new();
+            scene.update(Update::AddLock(Lock::WaitTurn(
+                flag.clone(),
+                this.id.clone(),
+            )));
+            Ok(YieldResult::new(LockReason::new_flag(flag)))
+        });
+        methods.add_method("_WaitJump", |_, this, (): ()| this.wait_height());
+        methods.add_method("_WaitHeight", |_, this, (): ()| this.wait_height());
         methods.add_method("_WaitMove", |_, this, (): ()| {
-            let mut scene = this.scene.lock().unwrap();
-            let abool = Arc::new(AtomicBool::new(false));
+            let mut scene = this.lock_scene()?;
+            let flag = WakeFlag::new();
             scene.update(Update::AddLock(Lock::WaitMove(
-                abool.clone(),
+                flag.clone(),
                 this.id.clone(),
             )));
-            Ok(YieldResult::new(LockReason::new_abool(abool)))
+            Ok(YieldResult::new(LockReason::new_flag(flag)))
         });
     }
 }
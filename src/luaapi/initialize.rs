@@ -1,8 +1,12 @@
 #![allow(clippy::trivially_copy_pass_by_ref)]
+#![allow(non_snake_case)]
 use mlua::Lua;
 
-use crate::gamedata::{FaceType, Scene, Speed, Time, Vec2_f64, Vec3_f64};
-use crate::luaapi::{SymAct, CH, CHARA, SCREEN, TASK, WINDOW};
+use crate::gamedata::{
+    Color, Easing, FaceType, MoveKind, PadButton, Scene, Speed, Time, Vec2_f64, Vec3_f64,
+    DIRECTIONS, MOTIONS,
+};
+use crate::luaapi::{SymAct, BLOCKING_METHOD_TABLE, CAMERA, CH, CHARA, PAD, SCREEN, TASK, WINDOW};
 use crate::RunningLua;
 use std::fmt::Write;
 use std::sync::{Arc, Mutex};
@@ -48,21 +52,25 @@ pub fn initialize_lua_environment(running_lua: &RunningLua, scene: &Arc<Mutex<Sc
         arguments_part.pop();
         let code_to_load = &format!(
             "function(this{}{})
-                coroutine.yield(this:_{}({}))
+                return coroutine.yield(this:_{}({}))
             end",
             if argument_number == 0 { "" } else { ", " },
             arguments_part,
             method_name,
             arguments_part
         );
-        println!("{}", code_to_load);
         let function: mlua::Function = lua.load(code_to_load).eval().unwrap();
-        let globals = lua.globals();
-        globals
+        lua.named_registry_value::<mlua::Table>(BLOCKING_METHOD_TABLE)
+            .unwrap()
+            .set(method_name, function.clone())
+            .unwrap();
+        lua.globals()
             .set(format!("OBJECT_{}", method_name), function)
             .unwrap();
     }
 
+    lua.set_named_registry_value(BLOCKING_METHOD_TABLE, lua.create_table().unwrap())
+        .unwrap();
     let globals = lua.globals();
 
     // debug function...
@@ -83,9 +91,8 @@ pub fn initialize_lua_environment(running_lua: &RunningLua, scene: &Arc<Mutex<Sc
         .unwrap();
     globals.set("CH", CH_function).unwrap();
     // add SymAct
-    let scene_clone = scene.clone();
     let SymAct_function = lua
-        .create_function(move |_, id: String| Ok(SymAct::new(scene_clone.clone(), id)))
+        .create_function(|_, id: String| Ok(SymAct::new(id)))
         .unwrap();
     globals.set("SymAct", SymAct_function).unwrap();
     // add Vector
@@ -103,13 +110,22 @@ pub fn initialize_lua_environment(running_lua: &RunningLua, scene: &Arc<Mutex<Sc
         .create_function(|_, speed: f64| Ok(Speed::new(speed)))
         .unwrap();
     globals.set("Speed", Speed_function).unwrap();
+    // add Color, with components between 0 and 1, opaque by default
+    let Color_function = lua
+        .create_function(|_, (r, g, b, a): (f32, f32, f32, Option<f32>)| {
+            Ok(Color::new(r, g, b, a.unwrap_or(1.0)))
+        })
+        .unwrap();
+    globals.set("Color", Color_function).unwrap();
     // add TimeSec
     let TimeSec_function = lua
         .create_function(|_, time_sec: f64| Ok(Time::new(time_sec)))
         .unwrap();
     globals.set("TimeSec", TimeSec_function).unwrap();
     // add TASK
-    globals.set("TASK", TASK::new(scene.clone())).unwrap();
+    globals
+        .set("TASK", TASK::new(scene.clone(), running_lua.tasks()))
+        .unwrap();
     // add SCREEN_A and SCREEN_B
     globals
         .set("SCREEN_A", SCREEN::new(scene.clone(), 0))
@@ -117,22 +133,111 @@ pub fn initialize_lua_environment(running_lua: &RunningLua, scene: &Arc<Mutex<Sc
     globals
         .set("SCREEN_B", SCREEN::new(scene.clone(), 1))
         .unwrap();
+    // add CAMERA
+    globals.set("CAMERA", CAMERA::new(scene.clone())).unwrap();
     // add WINDOW
     globals.set("WINDOW", WINDOW::new(scene.clone())).unwrap();
 
     // add PORTRAIT_TYPE
     let portrait_table = lua.create_table().unwrap();
-    portrait_table.set("NORMAL", FaceType::NORMAL).unwrap();
+    for (name, facetype) in FaceType::ALL {
+        portrait_table.set(name, facetype).unwrap();
+    }
     globals.set("FACE_TYPE", portrait_table).unwrap();
+    // add PAD and PAD_BUTTON
+    globals.set("PAD", PAD::new(scene.clone())).unwrap();
+    let button_table = lua.create_table().unwrap();
+    for (name, button) in PadButton::ALL {
+        button_table.set(name, button).unwrap();
+    }
+    globals.set("PAD_BUTTON", button_table).unwrap();
+    // add DIR
+    let direction_table = lua.create_table().unwrap();
+    for (name, direction) in DIRECTIONS {
+        direction_table.set(name, direction).unwrap();
+    }
+    globals.set("DIR", direction_table).unwrap();
+    // add MOTION
+    let motion_table = lua.create_table().unwrap();
+    for (name, _) in MOTIONS {
+        motion_table.set(name, name).unwrap();
+    }
+    globals.set("MOTION", motion_table).unwrap();
+    // add MOVE
+    let move_table = lua.create_table().unwrap();
+    for (name, _) in MoveKind::ALL {
+        move_table.set(name, name).unwrap();
+    }
+    globals.set("MOVE", move_table).unwrap();
+    // add EASE
+    let easing_table = lua.create_table().unwrap();
+    for (name, _) in Easing::ALL {
+        easing_table.set(name, name).unwrap();
+    }
+    globals.set("EASE", easing_table).unwrap();
 
-    // objects method that may return
+    // objects method, as OBJECT_Name(object, ...) functions. Blocking methods can also be called directly on the object.
     add_non_blocking_method(lua, "DynamicRemove", 1);
     add_non_blocking_method(lua, "DynamicLoad", 2);
     add_non_blocking_method(lua, "SetPosition", 1);
-    add_non_blocking_method(lua, "WalkTo", 2);
+    add_non_blocking_method(lua, "WalkTo", 3);
+    add_non_blocking_method(lua, "RunTo", 3);
+    add_non_blocking_method(lua, "SlideTo", 3);
+    add_non_blocking_method(lua, "MoveToRelative", 3);
+    add_non_blocking_method(lua, "MovePath", 4);
+    add_non_blocking_method(lua, "SetDir", 1);
+    add_non_blocking_method(lua, "TurnTo", 2);
+    add_non_blocking_method(lua, "DirTo", 2);
+    add_non_blocking_method(lua, "TurnToChara", 2);
+    add_non_blocking_method(lua, "Jump", 2);
+    add_non_blocking_method(lua, "SetHeight", 3);
+    add_non_blocking_method(lua, "SetMotion", 1);
+    add_non_blocking_method(lua, "SetEye", 1);
+    add_non_blocking_method(lua, "MoveTo", 3);
+    add_non_blocking_method(lua, "FollowChara", 1);
+    add_non_blocking_method(lua, "StopFollow", 0);
+    add_non_blocking_method(lua, "SetZoom", 3);
+    add_non_blocking_method(lua, "SetRotation", 3);
+    add_non_blocking_method(lua, "PlayMotion", 1);
     add_non_blocking_method(lua, "DrawFace", 4);
     add_non_blocking_method(lua, "RemoveFace", 0);
+    add_non_blocking_method(lua, "CloseMessage", 0);
+    add_non_blocking_method(lua, "Signal", 1);
+    add_non_blocking_method(lua, "ClearSignal", 1);
     add_blocking_method(lua, "Sleep", 1);
-    add_blocking_method(lua, "FadeOut", 2);
+    add_blocking_method(lua, "FadeOut", 3);
+    add_blocking_method(lua, "FadeIn", 3);
+    add_blocking_method(lua, "FadeToColor", 4);
+    add_blocking_method(lua, "Flash", 3);
+    add_blocking_method(lua, "SetTint", 4);
+    add_blocking_method(lua, "Quake", 3);
     add_blocking_method(lua, "WaitMove", 0);
+    add_blocking_method(lua, "WaitTurn", 0);
+    add_blocking_method(lua, "WaitMotion", 0);
+    add_blocking_method(lua, "WaitJump", 0);
+    add_blocking_method(lua, "WaitHeight", 0);
+    add_blocking_method(lua, "Talk", 2);
+    add_blocking_method(lua, "SwitchTalk", 1);
+    add_blocking_method(lua, "KeyWait", 0);
+    add_blocking_method(lua, "SelectChoice", 1);
+    add_blocking_method(lua, "WaitButton", 1);
+    add_blocking_method(lua, "WaitTask", 1);
+    add_blocking_method(lua, "WaitSignal", 1);
+
+    // wait for every task of the table, one after the other
+    let wait_all_tasks: mlua::Function = lua
+        .load(
+            "function(this, handles)
+                for _, handle in ipairs(handles) do
+                    this:WaitTask(handle)
+                end
+            end",
+        )
+        .eval()
+        .unwrap();
+    lua.named_registry_value::<mlua::Table>(BLOCKING_METHOD_TABLE)
+        .unwrap()
+        .set("WaitAllTasks", wait_all_tasks.clone())
+        .unwrap();
+    globals.set("OBJECT_WaitAllTasks", wait_all_tasks).unwrap();
 }
@@ -1,3 +1,6 @@
+mod blocking;
+pub use blocking::{add_blocking_method_lookup, BLOCKING_METHOD_TABLE};
+
 mod initialize;
 pub use initialize::initialize_lua_environment;
 
@@ -5,10 +8,14 @@ mod chara;
 pub use chara::CHARA;
 
 mod ch;
+pub(crate) use ch::read_position;
 pub use ch::CH;
 
+mod camera;
+pub use camera::CAMERA;
+
 mod task;
-pub use task::TASK;
+pub use task::{TaskHandle, TASK};
 
 mod screen;
 pub use screen::SCREEN;
@@ -16,5 +23,8 @@ pub use screen::SCREEN;
 mod window;
 pub use window::WINDOW;
 
+mod pad;
+pub use pad::PAD;
+
 mod symact;
 pub use symact::SymAct;
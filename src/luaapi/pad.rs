@@ -0,0 +1,41 @@
+use crate::gamedata::{Lock, PadButton, Scene, Update, Vec2_f64};
+use crate::luaapi::add_blocking_method_lookup;
+use crate::{LockReason, WakeFlag, YieldResult};
+use mlua::{UserData, UserDataMethods};
+use std::sync::{Arc, Mutex};
+
+/// The buttons and circle pad of the console
+pub struct PAD {
+    scene: Arc<Mutex<Scene>>,
+}
+
+impl PAD {
+    pub fn new(scene: Arc<Mutex<Scene>>) -> Self {
+        Self { scene }
+    }
+}
+
+impl UserData for PAD {
+    fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
+        add_blocking_method_lookup(methods);
+        // wait until the button is pressed, or any button if nil
+        methods.add_method("_WaitButton", |_, this, button: Option<PadButton>| {
+            let mut scene = this.scene.lock().unwrap();
+            let flag = WakeFlag::new();
+            scene.update(Update::AddLock(Lock::WaitButton(flag.clone(), button)));
+            Ok(YieldResult::new(LockReason::new_flag(flag)))
+        });
+
+        methods.add_method("IsPressed", |_, this, button: PadButton| {
+            Ok(this.scene.lock().unwrap().pad.is_pressed(button))
+        });
+
+        methods.add_method("IsHeld", |_, this, button: PadButton| {
+            Ok(this.scene.lock().unwrap().pad.is_held(button))
+        });
+
+        methods.add_method("GetDirection", |_, this, (): ()| {
+            Ok::<Vec2_f64, _>(this.scene.lock().unwrap().pad.direction)
+        });
+    }
+}
@@ -1,7 +1,8 @@
-use crate::gamedata::{Color, Lock, Scene, Time, Update};
-use crate::{LockReason, YieldResult};
+use crate::gamedata::{Color, Easing, Lock, Scene, Time, Update};
+use crate::luaapi::add_blocking_method_lookup;
+use crate::{LockReason, WakeFlag, YieldResult};
 use mlua::{UserData, UserDataMethods};
-use std::sync::{atomic::AtomicBool, Arc, Mutex};
+use std::sync::{Arc, Mutex};
 
 pub struct SCREEN {
     scene: Arc<Mutex<Scene>>,
@@ -12,33 +13,116 @@ impl SCREEN {
     pub fn new(scene: Arc<Mutex<Scene>>, id: u16) -> Self {
         Self { scene, id }
     }
+
+    /// Apply the update, then wait for the duration if sleep is ``true``
+    fn update_and_sleep(&self, update: Update, duration: Time, sleep: bool) -> YieldResult {
+        let mut scene = self.scene.lock().unwrap();
+        scene.update(update);
+        if sleep {
+            let flag = WakeFlag::new();
+            scene.update(Update::AddLock(Lock::Wait(flag.clone(), duration)));
+            YieldResult::new(LockReason::new_flag(flag))
+        } else {
+            YieldResult::new(LockReason::None)
+        }
+    }
+
+    /// The color covering the screen, set by the fades
+    fn fade_color(&self) -> Color {
+        self.scene.lock().unwrap().screens[self.id as usize]
+            .actual_color
+            .clone()
+    }
 }
+
+// Every effect wait for its end if sleep is true, and replace the one of the same kind in progress. The fades and the
+// tint start from the color shown, even in the middle of a previous transition.
 impl UserData for SCREEN {
     fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
-        methods.add_method("_FadeOut", |_, this, (duration, sleep): (Time, bool)| {
-            //TODO: what happen when multiple fadeout are called ?
-            let mut scene = this.scene.lock().unwrap();
-            scene.update(Update::SetScreenColor(
-                this.id,
-                Color {
-                    r: 0.0,
-                    g: 0.0,
-                    b: 0.0,
-                    a: 0.0,
-                },
-            ));
-            scene.update(Update::TransitionScreenColor(
-                this.id,
-                duration,
-                Color::black(),
-            ));
-            if sleep {
-                let abool = Arc::new(AtomicBool::new(false));
-                scene.update(Update::AddLock(Lock::Wait(abool.clone(), duration)));
-                Ok(YieldResult::new(LockReason::new_abool(abool)))
-            } else {
-                Ok(YieldResult::new(LockReason::None))
-            }
-        });
+        add_blocking_method_lookup(methods);
+        methods.add_method(
+            "_FadeOut",
+            |_, this, (duration, sleep, easing): (Time, bool, Option<Easing>)| {
+                Ok(this.update_and_sleep(
+                    Update::TransitionScreenColor(
+                        this.id,
+                        duration,
+                        Color::black(),
+                        easing.unwrap_or_default(),
+                    ),
+                    duration,
+                    sleep,
+                ))
+            },
+        );
+        // make the color covering the screen transparent, keeping its hue
+        methods.add_method(
+            "_FadeIn",
+            |_, this, (duration, sleep, easing): (Time, bool, Option<Easing>)| {
+                Ok(this.update_and_sleep(
+                    Update::TransitionScreenColor(
+                        this.id,
+                        duration,
+                        this.fade_color().with_alpha(0.0),
+                        easing.unwrap_or_default(),
+                    ),
+                    duration,
+                    sleep,
+                ))
+            },
+        );
+        methods.add_method(
+            "_FadeToColor",
+            |_, this, (color, duration, sleep, easing): (Color, Time, bool, Option<Easing>)| {
+                Ok(this.update_and_sleep(
+                    Update::TransitionScreenColor(
+                        this.id,
+                        duration,
+                        color,
+                        easing.unwrap_or_default(),
+                    ),
+                    duration,
+                    sleep,
+                ))
+            },
+        );
+        // cover the screen with the color (white if nil), fading out in the duration
+        methods.add_method(
+            "_Flash",
+            |_, this, (color, duration, sleep): (Option<Color>, Time, bool)| {
+                Ok(this.update_and_sleep(
+                    Update::FlashScreen(this.id, duration, color.unwrap_or_else(Color::white)),
+                    duration,
+                    sleep,
+                ))
+            },
+        );
+        // tint the scene below the fades, until changed. A transparent color remove it.
+        methods.add_method(
+            "_SetTint",
+            |_, this, (color, duration, sleep, easing): (Color, Time, bool, Option<Easing>)| {
+                Ok(this.update_and_sleep(
+                    Update::TransitionScreenTint(
+                        this.id,
+                        duration,
+                        color,
+                        easing.unwrap_or_default(),
+                    ),
+                    duration,
+                    sleep,
+                ))
+            },
+        );
+        // shake the screen, up to amplitude pixel, calming down until the end
+        methods.add_method(
+            "_Quake",
+            |_, this, (amplitude, duration, sleep): (f64, Time, bool)| {
+                Ok(this.update_and_sleep(
+                    Update::QuakeScreen(this.id, amplitude, duration),
+                    duration,
+                    sleep,
+                ))
+            },
+        );
     }
 }
@@ -1,26 +1,98 @@
 use crate::gamedata::{Lock, Scene, Time, Update};
+use crate::luaapi::add_blocking_method_lookup;
 use crate::LockReason;
+use crate::TaskManager;
+use crate::WakeFlag;
 use crate::YieldResult;
-use mlua::{UserData, UserDataMethods};
-use std::sync::{atomic::AtomicBool, Arc, Mutex};
+use mlua::{FromLua, Function, MetaMethod, UserData, UserDataMethods};
+use std::sync::{Arc, Mutex};
+
+/// A task started by ``TASK:Regist``, identified by its id in the `TaskManager`
+#[derive(Debug, Clone, Copy, PartialEq, FromLua)]
+pub struct TaskHandle {
+    pub id: u64,
+}
+
+impl UserData for TaskHandle {
+    fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
+        methods.add_meta_method(MetaMethod::ToString, |_, this, (): ()| {
+            Ok(format!("TaskHandle({})", this.id))
+        });
+        methods.add_meta_method(MetaMethod::Eq, |_, this, other: TaskHandle| {
+            Ok(*this == other)
+        });
+    }
+}
 
 pub struct TASK {
     scene: Arc<Mutex<Scene>>,
+    tasks: TaskManager,
 }
 
 impl TASK {
-    pub fn new(scene: Arc<Mutex<Scene>>) -> Self {
-        Self { scene }
+    pub fn new(scene: Arc<Mutex<Scene>>, tasks: TaskManager) -> Self {
+        Self { scene, tasks }
     }
 }
 
 impl UserData for TASK {
     fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
+        add_blocking_method_lookup(methods);
         methods.add_method("_Sleep", |_, this, time: Time| {
             let mut scene = this.scene.lock().unwrap();
-            let abool = Arc::new(AtomicBool::new(false));
-            scene.update(Update::AddLock(Lock::Wait(abool.clone(), time)));
-            Ok(YieldResult::new(LockReason::new_abool(abool)))
+            let flag = WakeFlag::new();
+            scene.update(Update::AddLock(Lock::Wait(flag.clone(), time)));
+            Ok(YieldResult::new(LockReason::new_flag(flag)))
+        });
+
+        // wait until the signal is raised. Return immediately if it already is.
+        methods.add_method("_WaitSignal", |_, this, name: String| {
+            let mut scene = this.scene.lock().unwrap();
+            let flag = WakeFlag::new();
+            scene.update(Update::AddLock(Lock::WaitSignal(flag.clone(), name)));
+            Ok(YieldResult::new(LockReason::new_flag(flag)))
+        });
+
+        // raise the signal, releasing every task waiting for it. It stay raised until cleared.
+        methods.add_method("Signal", |_, this, name: String| {
+            this.scene.lock().unwrap().update(Update::RaiseSignal(name));
+            Ok(())
+        });
+
+        methods.add_method("ClearSignal", |_, this, name: String| {
+            this.scene.lock().unwrap().update(Update::ClearSignal(name));
+            Ok(())
+        });
+
+        // start a new task, with an optional name and priority (see yammy_fork), and return its handle
+        methods.add_method(
+            "Regist",
+            |lua, this, (function, name, priority): (Function, Option<String>, Option<i64>)| {
+                let id = this.tasks.spawn(lua, function, name, priority)?;
+                Ok(TaskHandle { id })
+            },
+        );
+
+        // return the handle of a running task with this name, or nil
+        methods.add_method("Find", |_, this, name: String| {
+            Ok(this.tasks.find(&name).map(|id| TaskHandle { id }))
+        });
+
+        methods.add_method("IsRunning", |_, this, handle: TaskHandle| {
+            Ok(this.tasks.is_running(handle.id))
+        });
+
+        // kill the task. Return false if it already ended.
+        methods.add_method("Kill", |lua, this, handle: TaskHandle| {
+            debug!("killing the task {}", handle.id);
+            Ok(this.tasks.kill(lua, handle.id))
+        });
+
+        // wait until the task ended, either finished or killed
+        methods.add_method("_WaitTask", |_, this, handle: TaskHandle| {
+            Ok(YieldResult::new(LockReason::new_flag(
+                this.tasks.finished_flag(handle.id),
+            )))
         });
     }
 }
@@ -1,6 +1,8 @@
-use crate::gamedata::{FaceType, Portrait, Scene, Update};
-use crate::luaapi::SymAct;
+use crate::gamedata::{FaceType, Lock, Menu, Message, Portrait, Scene, Update};
+use crate::luaapi::{add_blocking_method_lookup, SymAct};
+use crate::{LockReason, ResumeValue, WakeFlag, YieldResult};
 use mlua::{UserData, UserDataMethods};
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 
 pub struct WINDOW {
@@ -13,8 +15,21 @@ impl WINDOW {
     }
 }
 
+/// Display the message, and wait for the player to read it
+fn talk(scene: &mut Scene, speaker: Option<String>, text: &str) -> YieldResult {
+    scene.update(Update::ShowMessage(Message::new(speaker, text)));
+    wait_confirmation(scene)
+}
+
+fn wait_confirmation(scene: &mut Scene) -> YieldResult {
+    let flag = WakeFlag::new();
+    scene.update(Update::AddLock(Lock::WaitMessage(flag.clone())));
+    YieldResult::new(LockReason::new_flag(flag))
+}
+
 impl UserData for WINDOW {
     fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
+        add_blocking_method_lookup(methods);
         methods.add_method(
             "DrawFace",
             |_, this, (x, y, actor, facetype): (f64, f64, SymAct, FaceType)| {
@@ -33,5 +48,76 @@ impl UserData for WINDOW {
             scene.update(Update::RemovePortrait);
             Ok(())
         });
+
+        methods.add_method(
+            "_Talk",
+            |_, this, (speaker, text): (Option<SymAct>, String)| {
+                let mut scene = this.scene.lock().unwrap();
+                Ok(talk(&mut scene, speaker.map(|speaker| speaker.id), &text))
+            },
+        );
+
+        // The text is choosen by the species of the hero, and said by the speaker of the previous message
+        methods.add_method(
+            "_SwitchTalk",
+            |_, this, texts: HashMap<String, String>| {
+                let mut scene = this.scene.lock().unwrap();
+                let hero_actor = scene
+                    .charas
+                    .get(&scene.resolve_chara_id("HERO"))
+                    .map(|hero| hero.actor.clone());
+                let text = match hero_actor.as_ref().and_then(|actor| texts.get(actor)) {
+                    Some(text) => text.clone(),
+                    None => {
+                        let mut keys: Vec<&String> = texts.keys().collect();
+                        keys.sort();
+                        let first_key = keys.first().ok_or_else(|| {
+                            mlua::Error::RuntimeError(
+                                "WINDOW:SwitchTalk need at least one text".into(),
+                            )
+                        })?;
+                        warn!(
+                            "no text for the hero species {:?} in WINDOW:SwitchTalk, using the one for {:?}",
+                            hero_actor, first_key
+                        );
+                        texts[*first_key].clone()
+                    }
+                };
+                let speaker = scene
+                    .message
+                    .as_ref()
+                    .and_then(|message| message.speaker.clone());
+                Ok(talk(&mut scene, speaker, &text))
+            },
+        );
+
+        methods.add_method("CloseMessage", |_, this, (): ()| {
+            let mut scene = this.scene.lock().unwrap();
+            scene.update(Update::CloseMessage);
+            Ok(())
+        });
+
+        // return the number of the choosen option, starting at 1
+        methods.add_method("_SelectChoice", |_, this, options: Vec<String>| {
+            if options.is_empty() {
+                return Err(mlua::Error::RuntimeError(
+                    "WINDOW:SelectChoice need at least one option".into(),
+                ));
+            };
+            let mut scene = this.scene.lock().unwrap();
+            scene.update(Update::ShowMenu(Menu::new(options)));
+            let flag = WakeFlag::new();
+            let choice = Arc::new(Mutex::new(ResumeValue::Nil));
+            scene.update(Update::AddLock(Lock::WaitChoice(
+                flag.clone(),
+                choice.clone(),
+            )));
+            Ok(YieldResult::new(LockReason::new_flag(flag)).with_resume_value(choice))
+        });
+
+        methods.add_method("_KeyWait", |_, this, (): ()| {
+            let mut scene = this.scene.lock().unwrap();
+            Ok(wait_confirmation(&mut scene))
+        });
     }
 }
@@ -1,37 +1,190 @@
-use pmd3d_script_runner::PistonRenderer;
+#[macro_use]
+extern crate log;
+
+use clap::Parser;
+use pmd3d_script_runner::gamedata::{NamingMode, Placement, Time};
+use pmd3d_script_runner::{
+    ClockMode, HeadlessRenderer, LoadError, Logic, OfflineError, OfflineOutput, OfflineRenderer,
+    PistonRenderer, RomfsScriptSource, ScriptCode, ScriptError, SpeciesRegistry,
+};
+use std::num::NonZeroU32;
+use std::path::PathBuf;
+use std::process::exit;
+
+/// The script or the data files couldn't be read
+const EXIT_IO_ERROR: i32 = 1;
+/// The script failed to load or to run
+const EXIT_SCRIPT_ERROR: i32 = 3;
+/// The rendered frames couldn't be written
+const EXIT_RECORD_ERROR: i32 = 4;
+
+/// Run a cutscene script from pokemon mystery dungeon: gate to infinity or super mystery dungeon
+#[derive(Parser)]
+#[command(version)]
+struct Args {
+    /// The lua script to run (source or bytecode), or the name of the scene when --romfs is used
+    script: PathBuf,
+    /// Take the script from this extracted romfs of super mystery dungeon or gate to infinity
+    #[arg(long)]
+    romfs: Option<PathBuf>,
+    /// Folder containing an extracted explorer of sky rom
+    #[arg(short, long, default_value = "data")]
+    data: PathBuf,
+    /// Width of the window or of the recorded frames, in pixel
+    #[arg(long, default_value_t = 640)]
+    width: u32,
+    /// Height of the window or of the recorded frames, in pixel
+    #[arg(long, default_value_t = 480)]
+    height: u32,
+    /// Skip the beginning of the scene, in second, in the window or the recording
+    #[arg(long, default_value_t = 0.0, conflicts_with = "headless")]
+    start_time: f64,
+    /// Stop after this much scene time, in second, for headless run and recording
+    #[arg(long)]
+    time_limit: Option<f64>,
+    /// The placement of the characters of the level, as the json produced from a PLB file
+    #[arg(long)]
+    plb: Option<PathBuf>,
+    /// Use the super mystery dungeon rules for character names: every name not declared by the placement refer to the same character
+    #[arg(long)]
+    psmd: bool,
+    /// A json file that map the actors to the sprites and portraits of explorer of sky, instead of the embedded one
+    #[arg(long)]
+    species: Option<PathBuf>,
+    /// Run the script without opening any window
+    #[arg(long, conflicts_with = "record")]
+    headless: bool,
+    /// Render the scene to this file instead of opening a window. A path ending with .gif create an animated gif, anything else a folder of png files
+    #[arg(long)]
+    record: Option<PathBuf>,
+    /// Number of frames per second, for headless run and recording, and of the frames run to skip the beginning with --start-time
+    #[arg(long, default_value_t = 60, value_parser = clap::value_parser!(u32).range(1..))]
+    framerate: u32,
+    /// In the window, advance the scene by fixed ticks at this rate (like 60 or 30, as the games) instead of by the time between two frames, so the scene evolve the same way on every computer
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    tick_rate: Option<u32>,
+}
+
+fn exit_with_load_error(err: LoadError) -> ! {
+    error!("{}", err);
+    match err {
+        LoadError::Data(_) => exit(EXIT_IO_ERROR),
+        LoadError::Script(_) => exit(EXIT_SCRIPT_ERROR),
+    }
+}
+
+fn exit_with_script_error(err: ScriptError) -> ! {
+    error!("the script failed: {}", err);
+    exit(EXIT_SCRIPT_ERROR);
+}
+
+/// Prepare the scene before the script start
+fn setup_scene(logic: &Logic, naming_mode: NamingMode, placement: &Option<Placement>) {
+    let mut scene = logic.scene.lock().unwrap();
+    scene.set_naming_mode(naming_mode);
+    if let Some(placement) = placement {
+        scene.load_placement(placement.clone());
+    };
+}
 
 fn main() {
     env_logger::init();
-    let mut renderer = PistonRenderer::new();
-    renderer.load(
-        r#"
-CHARA:DynamicLoad("HERO", "KIBAGO")
-CHARA:DynamicLoad("PARTNER", "TSUTAAJA")
-WINDOW:DrawFace(20, 88, SymAct("HERO"), FACE_TYPE.NORMAL)
-CH("HERO"):SetPosition(Vector(0, 0, 0))
-CH("HERO"):WalkTo(Vector2(3, 0), Speed(1))
-CH("PARTNER"):SetPosition(Vector(-1, 0, 0))
-CH("PARTNER"):WalkTo(Vector2(-1, 1), Speed(0.5))
-TASK:Sleep(TimeSec(2))
-CH("PARTNER"):WalkTo(Vector2(0, 0), Speed(1))
-TASK:Sleep(TimeSec(2))
-WINDOW:RemoveFace()
-while true do
-    CH("PARTNER"):WaitMove()
-    CH("PARTNER"):WalkTo(Vector2(0, 3), Speed(1))
-    CH("PARTNER"):WaitMove()
-    CH("PARTNER"):WalkTo(Vector2(0, 0), Speed(1))
-    CH("PARTNER"):WaitMove()
-    CH("PARTNER"):WalkTo(Vector2(-3, 0), Speed(1.5))
-    TASK:Sleep(TimeSec(3))
-    CH("PARTNER"):WalkTo(Vector2(1, -3), Speed(2))
-    TASK:Sleep(TimeSec(5))
-    CH("PARTNER"):WalkTo(Vector2(0, 0), Speed(1))
-    TASK:Sleep(TimeSec(3))
-end
--- Speed is in unit per second
-"#,
-    );
-    renderer.run();
-    renderer.close();
+    let args = Args::parse();
+
+    let script = match &args.romfs {
+        Some(romfs) => RomfsScriptSource::new(romfs).load_scene(&args.script.to_string_lossy()),
+        None => ScriptCode::open(&args.script),
+    };
+    let script = match script {
+        Ok(script) => script,
+        Err(err) => {
+            error!("impossible to read the script {:?}: {}", args.script, err);
+            exit(EXIT_IO_ERROR);
+        }
+    };
+    let placement = args.plb.as_ref().map(|plb| match Placement::open(plb) {
+        Ok(placement) => placement,
+        Err(err) => {
+            error!("impossible to read the placement file {:?}: {}", plb, err);
+            exit(EXIT_IO_ERROR);
+        }
+    });
+    let species = match &args.species {
+        Some(path) => match SpeciesRegistry::open(path) {
+            Ok(species) => species,
+            Err(err) => {
+                error!(
+                    "impossible to read the species registry {:?}: {}",
+                    path, err
+                );
+                exit(EXIT_IO_ERROR);
+            }
+        },
+        None => SpeciesRegistry::default(),
+    };
+    let naming_mode = if args.psmd {
+        NamingMode::Psmd
+    } else {
+        NamingMode::Gti
+    };
+    let start_time = Time::new(args.start_time);
+    // 0 is rejected by clap
+    let framerate = NonZeroU32::new(args.framerate).unwrap();
+    let time_limit = args.time_limit.map(Time::new);
+
+    if args.headless {
+        let mut renderer = HeadlessRenderer::new(Time::new(1.0 / args.framerate as f64));
+        if let Err(err) = renderer.load(&script) {
+            exit_with_load_error(LoadError::Script(err));
+        };
+        setup_scene(renderer.logic(), naming_mode, &placement);
+        match renderer.run(&mut (), time_limit) {
+            Ok(true) => (),
+            Ok(false) => info!("the time limit was reached before the end of the script"),
+            Err(err) => exit_with_script_error(err),
+        };
+    } else if let Some(record) = args.record {
+        let mut renderer = OfflineRenderer::new((args.width, args.height), framerate);
+        renderer.set_species_registry(species);
+        if let Err(err) = renderer.load(&script, &args.data) {
+            exit_with_load_error(err);
+        };
+        setup_scene(renderer.logic(), naming_mode, &placement);
+        if let Err(err) = renderer.skip(start_time) {
+            exit_with_script_error(err);
+        };
+        let output = if record.extension().map(|ext| ext == "gif").unwrap_or(false) {
+            OfflineOutput::Gif(record)
+        } else {
+            OfflineOutput::PngSequence(record)
+        };
+        match renderer.render(output, time_limit) {
+            Ok(frame_number) => info!("{} frames written", frame_number),
+            Err(OfflineError::Script(err)) => exit_with_script_error(err),
+            Err(err) => {
+                error!("{}", err);
+                exit(EXIT_RECORD_ERROR);
+            }
+        };
+    } else {
+        let mut renderer = PistonRenderer::new((args.width, args.height));
+        renderer.set_species_registry(species);
+        if let Err(err) = renderer.load(&script, &args.data) {
+            exit_with_load_error(err);
+        };
+        setup_scene(renderer.logic(), naming_mode, &placement);
+        if let Some(tick_rate) = args.tick_rate {
+            renderer
+                .logic_mut()
+                // 0 is rejected by clap
+                .set_clock_mode(ClockMode::Fixed(NonZeroU32::new(tick_rate).unwrap()));
+        };
+        if let Err(err) = renderer
+            .skip(start_time, framerate)
+            .and_then(|_| renderer.run())
+        {
+            exit_with_script_error(err);
+        };
+        renderer.close();
+    }
 }
@@ -12,43 +12,80 @@ pub struct ObjectOnScreen {
 /// Represent a 2D camera
 #[derive(Debug)]
 pub struct Camera {
+    /// How many pixel a position unit represent, without zoom
+    unit_scale: f64,
     scale: f64,
+    /// The point at the center of the screen, in unit
     x_unit: f64,
     y_unit: f64,
-    x_pixel: f64,
-    y_pixel: f64,
     x_pixel_screen_center: f64,
     y_pixel_screen_center: f64,
     angle: f64, // radians
 }
 
 impl Camera {
-    pub fn new(scale: f64, (x_unit, y_unit): (f64, f64), angle: f64) -> Self {
+    pub fn new(unit_scale: f64) -> Self {
         Self {
-            scale,
-            x_unit,
-            y_unit,
-            x_pixel: x_unit * scale,
-            y_pixel: y_unit * scale,
+            unit_scale,
+            scale: unit_scale,
+            x_unit: 0.0,
+            y_unit: 0.0,
             x_pixel_screen_center: 0.0,
             y_pixel_screen_center: 0.0,
-            angle,
+            angle: 0.0,
         }
     }
 
-    /// Compute where to draw something, based in its x and y coordinate (in unit) and it's angle (in radians)
+    /// Look at the scene like its camera
+    pub fn look_from(&mut self, camera: &crate::gamedata::Camera) {
+        self.scale = self.unit_scale * camera.zoom;
+        self.x_unit = camera.focus.x;
+        self.y_unit = -camera.focus.y;
+        self.angle = camera.angle;
+    }
+
+    /// Compute where to draw something, based in its x and y coordinate (in unit, y going down) and it's angle (in radians)
     /// Return: x and y, in pixel, and the drawing angle, also in radians.
     pub fn compute_display_data(&self, (x_unit, y_unit): (f64, f64), angle: f64) -> ObjectOnScreen {
-        //TODO: take into account the initial angle
+        let (x_relative, y_relative) = (x_unit - self.x_unit, y_unit - self.y_unit);
+        let (sin, cos) = self.angle.sin_cos();
         ObjectOnScreen {
-            x_pixel: self.x_pixel + x_unit * self.scale + self.x_pixel_screen_center,
-            y_pixel: self.y_pixel + y_unit * self.scale + self.y_pixel_screen_center,
-            angle,
+            x_pixel: (x_relative * cos - y_relative * sin) * self.scale
+                + self.x_pixel_screen_center,
+            y_pixel: (x_relative * sin + y_relative * cos) * self.scale
+                + self.y_pixel_screen_center,
+            angle: angle - self.angle,
         }
     }
 
+    /// Return how many pixel a position unit represent
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
     pub fn set_screen_size(&mut self, (x_pixel, y_pixel): (f64, f64)) {
         self.x_pixel_screen_center = x_pixel / 2.0;
         self.y_pixel_screen_center = y_pixel / 2.0;
     }
 }
+
+#[test]
+fn test_camera_display_data() {
+    use crate::gamedata::Vec2_f64;
+    use std::f64::consts::FRAC_PI_2;
+    let mut camera = Camera::new(100.0);
+    camera.set_screen_size((400.0, 200.0));
+    let mut view = crate::gamedata::Camera::default();
+    view.focus = Vec2_f64::new(1.0, 1.0);
+    view.zoom = 2.0;
+    camera.look_from(&view);
+    let display_data = camera.compute_display_data((2.0, -1.0), 0.0);
+    assert_eq!((display_data.x_pixel, display_data.y_pixel), (400.0, 100.0));
+    // turned clockwise: what was at the right of the focus is now below it
+    view.angle = FRAC_PI_2;
+    camera.look_from(&view);
+    let display_data = camera.compute_display_data((2.0, -1.0), 0.0);
+    assert!((display_data.x_pixel - 200.0).abs() < 1e-9);
+    assert!((display_data.y_pixel - 300.0).abs() < 1e-9);
+    assert_eq!(display_data.angle, -FRAC_PI_2);
+}
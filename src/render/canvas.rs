@@ -0,0 +1,170 @@
+use crate::render::WanHostFragment;
+use ::image::{Rgba, RgbaImage};
+use piston_window::*;
+use pmd_wan::FragmentFlip;
+
+/// A surface where the scene can be drawn. Coordinate are in pixel, with (0, 0) at the top left corner.
+///
+/// Every renderer draw the scene through this trait, so they all place sprites the same way.
+pub trait Canvas {
+    /// Size of the canvas, in pixel
+    fn size(&self) -> (f64, f64);
+
+    /// Fill the whole canvas with the given color
+    fn clear(&mut self, color: [f32; 4]);
+
+    /// Draw a rectangle, blended with what is already drawn. rect is x, y, width, height.
+    fn fill_rectangle(&mut self, color: [f32; 4], rect: [f64; 4]);
+
+    /// Draw a fragment of a wan sprite, with its top left corner at coord
+    fn draw_fragment(
+        &mut self,
+        fragment: &WanHostFragment,
+        coord: &(f64, f64),
+        scale: f64,
+        flip: FragmentFlip,
+    );
+}
+
+/// Draw on a piston window
+pub struct PistonCanvas<'a, 'b> {
+    pub graphic: &'a mut G2d<'b>,
+    pub context: &'a Context,
+}
+
+impl Canvas for PistonCanvas<'_, '_> {
+    fn size(&self) -> (f64, f64) {
+        let viewport = self.context.viewport.as_ref().unwrap();
+        (viewport.draw_size[0] as f64, viewport.draw_size[1] as f64)
+    }
+
+    fn clear(&mut self, color: [f32; 4]) {
+        clear(color, self.graphic);
+    }
+
+    fn fill_rectangle(&mut self, color: [f32; 4], rect: [f64; 4]) {
+        rectangle(color, rect, self.context.transform, self.graphic);
+    }
+
+    fn draw_fragment(
+        &mut self,
+        fragment: &WanHostFragment,
+        coord: &(f64, f64),
+        scale: f64,
+        flip: FragmentFlip,
+    ) {
+        let texture = fragment
+            .texture
+            .as_ref()
+            .expect("the sprite was loaded without texture");
+        let scaled_width = scale * fragment.width();
+        let scaled_height = scale * fragment.height();
+        image(
+            texture,
+            self.context
+                .transform
+                .trans(
+                    coord.0 + if flip.flip_h { scaled_width } else { 0.0 },
+                    coord.1 + if flip.flip_v { scaled_height } else { 0.0 },
+                )
+                .scale(
+                    if flip.flip_h { -scale } else { scale },
+                    if flip.flip_v { -scale } else { scale },
+                ),
+            self.graphic,
+        );
+    }
+}
+
+/// Blend `color` over `pixel`, like the piston renderer does
+fn blend_pixel(pixel: &mut Rgba<u8>, color: [f32; 4]) {
+    let alpha = color[3].clamp(0.0, 1.0);
+    if alpha <= 0.0 {
+        return;
+    };
+    let destination_alpha = pixel[3] as f32 / 255.0;
+    let result_alpha = alpha + destination_alpha * (1.0 - alpha);
+    for channel in 0..3 {
+        let destination = pixel[channel] as f32 / 255.0;
+        let value = (color[channel] * alpha + destination * destination_alpha * (1.0 - alpha))
+            / result_alpha;
+        pixel[channel] = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    }
+    pixel[3] = (result_alpha * 255.0).round() as u8;
+}
+
+/// Return the range of pixel covered by a segment starting at start of lenght lenght, clamped to max
+fn pixel_range(start: f64, lenght: f64, max: u32) -> std::ops::Range<u32> {
+    let first = start.round().max(0.0).min(max as f64) as u32;
+    let last = (start + lenght).round().max(0.0).min(max as f64) as u32;
+    first..last
+}
+
+/// Software rendering, on an image in memory
+impl Canvas for RgbaImage {
+    fn size(&self) -> (f64, f64) {
+        (self.width() as f64, self.height() as f64)
+    }
+
+    fn clear(&mut self, color: [f32; 4]) {
+        let pixel = Rgba(color.map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8));
+        for destination in self.pixels_mut() {
+            *destination = pixel;
+        }
+    }
+
+    fn fill_rectangle(&mut self, color: [f32; 4], rect: [f64; 4]) {
+        for y in pixel_range(rect[1], rect[3], self.height()) {
+            for x in pixel_range(rect[0], rect[2], self.width()) {
+                blend_pixel(self.get_pixel_mut(x, y), color);
+            }
+        }
+    }
+
+    fn draw_fragment(
+        &mut self,
+        fragment: &WanHostFragment,
+        coord: &(f64, f64),
+        scale: f64,
+        flip: FragmentFlip,
+    ) {
+        let source = &fragment.image;
+        let scaled_width = scale * fragment.width();
+        let scaled_height = scale * fragment.height();
+        for y in pixel_range(coord.1, scaled_height, self.height()) {
+            let source_y = (((y as f64 + 0.5 - coord.1) / scale) as u32).min(source.height() - 1);
+            let source_y = if flip.flip_v {
+                source.height() - 1 - source_y
+            } else {
+                source_y
+            };
+            for x in pixel_range(coord.0, scaled_width, self.width()) {
+                let source_x =
+                    (((x as f64 + 0.5 - coord.0) / scale) as u32).min(source.width() - 1);
+                let source_x = if flip.flip_h {
+                    source.width() - 1 - source_x
+                } else {
+                    source_x
+                };
+                let source_pixel = source.get_pixel(source_x, source_y);
+                blend_pixel(
+                    self.get_pixel_mut(x, y),
+                    source_pixel.0.map(|channel| channel as f32 / 255.0),
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_software_canvas_fill() {
+    let mut canvas = RgbaImage::new(4, 4);
+    canvas.clear([1.0, 1.0, 1.0, 1.0]);
+    canvas.fill_rectangle([0.0, 0.0, 0.0, 1.0], [1.0, 1.0, 2.0, 2.0]);
+    assert_eq!(canvas.get_pixel(0, 0), &Rgba([255, 255, 255, 255]));
+    assert_eq!(canvas.get_pixel(1, 1), &Rgba([0, 0, 0, 255]));
+    assert_eq!(canvas.get_pixel(2, 2), &Rgba([0, 0, 0, 255]));
+    assert_eq!(canvas.get_pixel(3, 3), &Rgba([255, 255, 255, 255]));
+    canvas.fill_rectangle([0.0, 0.0, 0.0, 0.5], [0.0, 0.0, 4.0, 4.0]);
+    assert_eq!(canvas.get_pixel(0, 0), &Rgba([128, 128, 128, 255]));
+}
@@ -1,26 +1,56 @@
-use crate::gamedata::Time;
-use crate::render::{WanHandler, WanSprite};
-use piston_window::*;
+use crate::gamedata::{Motion, Time};
+use crate::render::{Canvas, SpeciesEntry, WanHandler, WanSprite};
 use std::rc::Rc;
 
+/// Return the one of the 8 directions of the WAN animations closest to the angle (in radians, 0 facing right,
+/// PI/2 facing the top). The directions start facing down, and turn counterclockwise.
+fn wan_direction(angle: f64) -> usize {
+    ((angle / std::f64::consts::FRAC_PI_4).round() as i64 + 10).rem_euclid(8) as usize
+}
+
+/// The color of the marker drawn above the characters displayed with the placeholder sprite
+const PLACEHOLDER_MARKER_COLOR: [f32; 4] = [1.0, 0.0, 1.0, 1.0];
+/// The size of the side of the marker, and how high above the character it is, in pixel of the sprite
+const PLACEHOLDER_MARKER_SIZE: f64 = 6.0;
+const PLACEHOLDER_MARKER_ELEVATION: f64 = 40.0;
+
+/// Draw a square above the character at coord, to show it's species is missing from the registry
+fn draw_placeholder_marker<C: Canvas>(canvas: &mut C, coord: &(f64, f64), scale: f64, height: f64) {
+    let size = PLACEHOLDER_MARKER_SIZE * scale;
+    canvas.fill_rectangle(
+        PLACEHOLDER_MARKER_COLOR,
+        [
+            coord.0 - size / 2.0,
+            coord.1 - height - PLACEHOLDER_MARKER_ELEVATION * scale,
+            size,
+            size,
+        ],
+    );
+}
+
 pub struct CharacterSprite {
     handler: WanHandler,
+    species: SpeciesEntry,
     change_with_angle: bool,
     anim_id: usize,
+    /// The animation is played once, and its end wasn't reported yet
+    one_shot: bool,
     time_before_next_frame: Time,
     time_between_frame: Time,
 }
 
 impl CharacterSprite {
-    pub fn new_from_wan_sprite(sprite: Rc<WanSprite>) -> CharacterSprite {
-        Self::new_from_wan_handler(WanHandler::new(sprite, true))
+    pub fn new_from_wan_sprite(sprite: Rc<WanSprite>, species: SpeciesEntry) -> CharacterSprite {
+        Self::new_from_wan_handler(WanHandler::new(sprite, true), species)
     }
 
-    pub fn new_from_wan_handler(handler: WanHandler) -> CharacterSprite {
+    pub fn new_from_wan_handler(handler: WanHandler, species: SpeciesEntry) -> CharacterSprite {
         let mut result = CharacterSprite {
             handler,
+            species,
             change_with_angle: false,
             anim_id: 0,
+            one_shot: false,
             time_before_next_frame: Time::new(0.1 / 6.0),
             time_between_frame: Time::new(0.1 / 6.0),
         };
@@ -39,27 +69,60 @@ impl CharacterSprite {
         self.handler.start_animation(anim_id, 0);
     }
 
-    pub fn draw(
+    pub fn species(&self) -> &SpeciesEntry {
+        &self.species
+    }
+
+    /// Play a named animation (like `ANIMATION_WALK`) in loop, using the animation id of the species.
+    ///
+    /// An animation the sprite doesn't have is replaced by the animation 0.
+    pub fn play_animation(&mut self, name: &str) {
+        let mut anim_id = self.species.animation(name);
+        if anim_id >= self.handler.len_animations() {
+            warn!(
+                "the sprite doesn't have the animation {} for {:?}, using the animation 0",
+                anim_id, name
+            );
+            anim_id = 0;
+        };
+        self.one_shot = false;
+        self.set_animation(anim_id, true);
+    }
+
+    /// Play the `Motion`, either once or in loop
+    pub fn play_motion(&mut self, motion: &Motion) {
+        self.play_animation(&motion.name);
+        self.one_shot = !motion.looping;
+    }
+
+    /// Return ``true`` once, when the animation played once finished
+    pub fn take_finished_motion(&mut self) -> bool {
+        if self.one_shot && self.handler.is_finished() {
+            self.one_shot = false;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Draw the character at coord, facing angle (in radians), raised by height pixels above its shadow.
+    /// A marker is drawn above the placeholder sprite.
+    pub fn draw<C: Canvas>(
         &mut self,
-        graphic: &mut G2d,
-        context: &Context,
+        canvas: &mut C,
         coord: &(f64, f64),
         scale: f64,
         angle: f64,
+        height: f64,
     ) {
         if self.change_with_angle {
-            let anim_angle_change =
-                (((angle / std::f64::consts::FRAC_PI_4).round() + 4.0) as usize + 6) % 8;
-            let anim_angle_change = if anim_angle_change == 8 {
-                0
-            } else {
-                anim_angle_change
-            };
-
             self.handler
-                .transmute_animation(self.anim_id, anim_angle_change);
+                .transmute_animation(self.anim_id, wan_direction(angle));
+        };
+        self.handler.draw_frame(canvas, coord, scale, height);
+        if self.species.is_placeholder {
+            draw_placeholder_marker(canvas, coord, scale, height);
         };
-        self.handler.draw_frame(graphic, context, coord, scale);
     }
 
     pub fn time_spent(&mut self, time: Time) {
@@ -70,3 +133,25 @@ impl CharacterSprite {
         }
     }
 }
+
+#[test]
+fn test_wan_direction() {
+    use std::f64::consts::PI;
+    assert_eq!(wan_direction(-PI / 2.0), 0);
+    assert_eq!(wan_direction(0.0), 2);
+    assert_eq!(wan_direction(PI / 2.0), 4);
+    assert_eq!(wan_direction(PI), 6);
+    assert_eq!(wan_direction(1.5 * PI), 0);
+    // any angle, even after many turns
+    assert_eq!(wan_direction(-7.0 * PI / 4.0), 3);
+    assert_eq!(wan_direction(4.0 * PI + 0.1), 2);
+}
+
+#[test]
+fn test_placeholder_marker() {
+    use ::image::{Rgba, RgbaImage};
+    let mut canvas = RgbaImage::new(20, 50);
+    draw_placeholder_marker(&mut canvas, &(10.0, 48.0), 1.0, 2.0);
+    assert_eq!(canvas.get_pixel(10, 8), &Rgba([255, 0, 255, 255]));
+    assert_eq!(canvas.get_pixel(10, 20), &Rgba([0, 0, 0, 0]));
+}
@@ -0,0 +1,128 @@
+use crate::gamedata::Scene;
+use crate::render::{draw_menu, draw_message, Camera, Canvas, CharacterSprite, PortraitStore};
+use pmd_wan::FragmentFlip;
+use std::collections::HashMap;
+
+/// The size of the upper screen of the 3DS, in pixel
+const SCREEN_3DS_SIZE: (f64, f64) = (400.0, 240.0);
+
+/// Return the scale and the offset to apply to a coordinate on the 3DS screen to place it on a screen of
+/// the given size. The 3DS screen is scaled as much as possible while keeping its aspect ratio, and centered.
+fn screen_3ds_transform(screen_size: (f64, f64)) -> (f64, (f64, f64)) {
+    let scale = f64::min(
+        screen_size.0 / SCREEN_3DS_SIZE.0,
+        screen_size.1 / SCREEN_3DS_SIZE.1,
+    );
+    let offset = (
+        (screen_size.0 - SCREEN_3DS_SIZE.0 * scale) / 2.0,
+        (screen_size.1 - SCREEN_3DS_SIZE.1 * scale) / 2.0,
+    );
+    (scale, offset)
+}
+
+/// Draw the actual state of the scene on the canvas, as seen by the camera
+pub fn draw_scene<C: Canvas>(
+    canvas: &mut C,
+    scene: &Scene,
+    characters_sprite: &mut HashMap<String, CharacterSprite>,
+    portraits: &PortraitStore,
+    camera: &mut Camera,
+) {
+    let (screen_x, screen_y) = canvas.size();
+    camera.set_screen_size((screen_x, screen_y));
+    camera.look_from(&scene.camera);
+    //clear the screen
+    canvas.clear([1.0; 4]);
+    let (scale, offset) = screen_3ds_transform((screen_x, screen_y));
+    let front_screen = &scene.screens[0];
+    let quake_offset = front_screen.quake_offset();
+    //draw characters
+    for (charaid, chara) in scene.charas.iter() {
+        // the sprite is only created once the renderer received the ``AddChara`` update, after the first frame
+        let sprite = match characters_sprite.get_mut(charaid) {
+            Some(sprite) => sprite,
+            None => continue,
+        };
+        let display_data =
+            camera.compute_display_data((chara.position.x, -chara.position.y), chara.angle);
+        sprite.draw(
+            canvas,
+            &(
+                display_data.x_pixel + quake_offset.0 * scale,
+                display_data.y_pixel + quake_offset.1 * scale,
+            ),
+            camera.scale() / 32.0,
+            display_data.angle,
+            chara.position.z * camera.scale(),
+        );
+    }
+    //tint the scene
+    let tint = &front_screen.tint;
+    if tint.a > 0.0 {
+        canvas.fill_rectangle(
+            [tint.r, tint.g, tint.b, tint.a],
+            [0.0, 0.0, screen_x, screen_y],
+        );
+    };
+    //draw the portrait
+    if let (Some(portrait), Some(image)) = (&scene.portrait, &portraits.displayed) {
+        canvas.draw_fragment(
+            image,
+            &(
+                offset.0 + portrait.coord.0 * scale,
+                offset.1 + portrait.coord.1 * scale,
+            ),
+            scale,
+            FragmentFlip::from_bools(false, false),
+        );
+    };
+    //draw the text box
+    if let Some(message) = &scene.message {
+        draw_message(canvas, message, scale, offset);
+    };
+    if let Some(menu) = &scene.menu {
+        draw_menu(canvas, menu, scale, offset);
+    };
+    //render the front screen
+    let color = &front_screen.actual_color;
+    canvas.fill_rectangle(
+        [color.r, color.g, color.b, color.a],
+        [0.0, 0.0, screen_x, screen_y],
+    );
+    if let Some(flash) = front_screen.flash_color() {
+        canvas.fill_rectangle(
+            [flash.r, flash.g, flash.b, flash.a],
+            [0.0, 0.0, screen_x, screen_y],
+        );
+    };
+}
+
+#[test]
+fn test_screen_3ds_transform() {
+    assert_eq!(screen_3ds_transform((400.0, 240.0)), (1.0, (0.0, 0.0)));
+    assert_eq!(screen_3ds_transform((800.0, 600.0)), (2.0, (0.0, 60.0)));
+}
+
+#[test]
+fn test_draw_scene_before_sprites() {
+    use crate::gamedata::{PlacedActor, Placement, Vec3_f64};
+    use ::image::RgbaImage;
+    let mut scene = Scene::default();
+    scene.load_placement(Placement {
+        actors: vec![PlacedActor {
+            name: "HERO".into(),
+            actor: Some("KIBAGO".into()),
+            position: Vec3_f64::new(1.0, 2.0, 0.0),
+            direction: 0.0,
+        }],
+    });
+    assert!(scene.charas.contains_key("HERO"));
+    // drawn before the first frame gave the sprites to the renderer
+    draw_scene(
+        &mut RgbaImage::new(40, 24),
+        &scene,
+        &mut HashMap::new(),
+        &PortraitStore::default(),
+        &mut Camera::new(100.0),
+    );
+}
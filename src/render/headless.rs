@@ -0,0 +1,173 @@
+use crate::gamedata::Time;
+use crate::render::{execute_frame, UpdateReceiver};
+use crate::Input;
+use crate::Logic;
+use crate::ScriptError;
+
+/// A renderer that doesn't open any window. It run the `Logic` with a fixed timestep, as fast as possible.
+///
+/// Usefull to run a script where no display is available, or to check that a script run fine.
+/// As there is no player, the buttons waited by the script are pressed automatically, like to confirm a message
+/// once it is entirely displayed.
+pub struct HeadlessRenderer {
+    logic: Option<Logic>,
+    timestep: Time,
+    elapsed: Time,
+    confirm_delay: Time,
+    /// The time spent waiting for a button press
+    confirmation_wait: Time,
+}
+
+impl Default for HeadlessRenderer {
+    fn default() -> Self {
+        Self::new(Time::new(1.0 / 60.0))
+    }
+}
+
+impl HeadlessRenderer {
+    /// Create a new `HeadlessRenderer`, that will advance the scene by `timestep` each frame
+    pub fn new(timestep: Time) -> Self {
+        Self {
+            logic: None,
+            timestep,
+            elapsed: Time::new(0.0),
+            confirm_delay: Time::new(0.0),
+            confirmation_wait: Time::new(0.0),
+        }
+    }
+
+    /// Choose how long to wait before automatically pressing a button, like to confirm a message. Immediate by default.
+    pub fn set_confirm_delay(&mut self, confirm_delay: Time) {
+        self.confirm_delay = confirm_delay;
+    }
+
+    /// Load the script. Return an error if it can't be compiled.
+    pub fn load<S: AsRef<[u8]> + ?Sized>(&mut self, code: &S) -> Result<(), ScriptError> {
+        self.logic = Some(Logic::new(code)?);
+        self.elapsed = Time::new(0.0);
+        Ok(())
+    }
+
+    pub fn logic(&self) -> &Logic {
+        self.logic.as_ref().unwrap()
+    }
+
+    /// Return the time simulated since the script was loaded
+    pub fn elapsed(&self) -> Time {
+        self.elapsed
+    }
+
+    /// Return ``true`` if the script has finished its execution
+    pub fn is_finished(&self) -> bool {
+        self.logic().is_finished()
+    }
+
+    /// Run a single frame, and give the resulting `Update`s to `receiver`
+    pub fn step<R: UpdateReceiver + ?Sized>(
+        &mut self,
+        receiver: &mut R,
+    ) -> Result<(), ScriptError> {
+        let logic = self.logic.as_mut().unwrap();
+        self.elapsed += self.timestep;
+        let mut input = Input::new(self.timestep.get_time());
+        match logic.expected_button() {
+            Some(button) => {
+                self.confirmation_wait += self.timestep;
+                if self.confirmation_wait >= self.confirm_delay {
+                    input.pad.press(button);
+                    self.confirmation_wait = Time::new(0.0);
+                };
+            }
+            None => self.confirmation_wait = Time::new(0.0),
+        };
+        execute_frame(logic, input, receiver)
+    }
+
+    /// Run the script until it finish, or until `time_limit` of scene time is elapsed.
+    ///
+    /// Return ``true`` if the script finished, ``false`` if it was interrupted by the time limit.
+    /// Without a time limit, this never return for a script that never finish.
+    /// Stop at the first error raised by the script.
+    pub fn run<R: UpdateReceiver + ?Sized>(
+        &mut self,
+        receiver: &mut R,
+        time_limit: Option<Time>,
+    ) -> Result<bool, ScriptError> {
+        loop {
+            self.step(receiver)?;
+            if self.is_finished() {
+                return Ok(true);
+            };
+            if let Some(time_limit) = time_limit {
+                if self.elapsed >= time_limit {
+                    return Ok(false);
+                };
+            };
+        }
+    }
+}
+
+#[test]
+fn test_headless_run_to_completion() {
+    use crate::gamedata::Update;
+    let mut renderer = HeadlessRenderer::new(Time::new(0.1));
+    renderer
+        .load(
+            "CHARA:DynamicLoad(\"HERO\", \"KIBAGO\")
+        TASK:Sleep(TimeSec(1))
+        CHARA:DynamicRemove(\"HERO\")",
+        )
+        .unwrap();
+    let mut updates: Vec<Update> = Vec::new();
+    assert!(renderer.run(&mut updates, Some(Time::new(10.0))).unwrap());
+    assert!(renderer.elapsed() < Time::new(2.0));
+    assert!(updates
+        .iter()
+        .any(|update| matches!(update, Update::DelChara(id) if id == "HERO")));
+}
+
+#[test]
+fn test_headless_time_limit() {
+    let mut renderer = HeadlessRenderer::new(Time::new(0.1));
+    renderer
+        .load(
+            "while true do
+            TASK:Sleep(TimeSec(1))
+        end",
+        )
+        .unwrap();
+    assert!(!renderer.run(&mut (), Some(Time::new(5.0))).unwrap());
+    assert!(!renderer.is_finished());
+    assert!(renderer.elapsed() >= Time::new(5.0));
+}
+
+#[test]
+fn test_headless_auto_confirm() {
+    let mut renderer = HeadlessRenderer::new(Time::new(0.1));
+    renderer.set_confirm_delay(Time::new(1.0));
+    renderer
+        .load(
+            "WINDOW:Talk(SymAct(\"HERO\"), \"[CS:R]Hello[CR][K] world\")
+        WINDOW:CloseMessage()",
+        )
+        .unwrap();
+    assert!(renderer.run(&mut (), Some(Time::new(10.0))).unwrap());
+    // two confirmations, each after the delay
+    assert!(renderer.elapsed() > Time::new(2.0));
+    assert!(renderer.logic().scene.lock().unwrap().message.is_none());
+}
+
+#[test]
+fn test_headless_script_error() {
+    let mut renderer = HeadlessRenderer::new(Time::new(0.1));
+    renderer
+        .load(
+            "TASK:Sleep(TimeSec(1))
+        local a = nil
+        a.b = 1",
+        )
+        .unwrap();
+    let err = renderer.run(&mut (), Some(Time::new(5.0))).unwrap_err();
+    assert_eq!(err.line, Some(3));
+    assert!(renderer.is_finished());
+}
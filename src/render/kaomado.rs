@@ -0,0 +1,212 @@
+use crate::gamedata::FaceType;
+use crate::render::WanHostFragment;
+use ::image::{Rgba, RgbaImage};
+use piston_window::G2dTextureContext;
+use pmd_pkdpx::{decompress_px, PXError};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Cursor};
+use std::path::Path;
+use std::rc::Rc;
+
+/// The width and height of a portrait, in pixel
+const PORTRAIT_SIZE: u32 = 40;
+/// The number of portrait slots for each entry
+const SLOT_NUMBER: usize = 40;
+/// Each portrait start with a 16 colors palette, in RGB
+const PALETTE_SIZE: usize = 16 * 3;
+/// The lenght of the header of an AT4PX compressed file
+const AT4PX_HEADER_SIZE: usize = 18;
+/// The size of a tile of the image, in pixel
+const TILE_SIZE: u32 = 8;
+
+/// The portraits of explorer of sky, from ``FONT/kaomado.kao``.
+///
+/// The file start with a table with one entry for each species (the first one being empty), each entry having
+/// `SLOT_NUMBER` pointers. A pointer is positive if the portrait exist. It point to a palette, followed by the
+/// AT4PX compressed 4 bits per pixel image, made of 8x8 tiles.
+pub struct Kaomado {
+    content: Vec<u8>,
+}
+
+impl Kaomado {
+    pub fn new(content: Vec<u8>) -> Self {
+        Self { content }
+    }
+
+    pub fn open(path: &Path) -> io::Result<Self> {
+        Ok(Self::new(fs::read(path)?))
+    }
+
+    fn read_u32(&self, offset: usize) -> Option<u32> {
+        let bytes = self.content.get(offset..offset + 4)?;
+        Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// Return the portrait at the given entry and slot, or ``None`` if it doesn't exist
+    pub fn get_portrait(&self, entry: usize, slot: usize) -> Result<Option<RgbaImage>, PXError> {
+        if slot >= SLOT_NUMBER {
+            return Ok(None);
+        };
+        let pointer = match self.read_u32((entry * SLOT_NUMBER + slot) * 4) {
+            Some(pointer) => pointer as i32,
+            None => return Ok(None),
+        };
+        if pointer <= 0 {
+            return Ok(None);
+        };
+        let palette_start = pointer as usize;
+        let image_start = palette_start + PALETTE_SIZE;
+        let truncated = || PXError::IOError(io::ErrorKind::UnexpectedEof.into());
+        let palette = self
+            .content
+            .get(palette_start..image_start)
+            .ok_or_else(truncated)?;
+        let header = self
+            .content
+            .get(image_start..image_start + AT4PX_HEADER_SIZE)
+            .ok_or_else(truncated)?;
+        let container_lenght = u16::from_le_bytes([header[5], header[6]]) as usize;
+        let compressed = self
+            .content
+            .get(image_start..image_start + container_lenght)
+            .ok_or_else(truncated)?;
+        let pixels = decompress_px(Cursor::new(compressed))?;
+        Ok(Some(decode_tiled_image(&pixels, palette)))
+    }
+}
+
+/// Open the ``FONT/kaomado.kao`` file of an extracted explorer of sky rom. As portraits are optional, only a warning is logged if it can't be read.
+pub fn load_kaomado(data_path: &Path) -> Option<Kaomado> {
+    let path = data_path.join("FONT/kaomado.kao");
+    match Kaomado::open(&path) {
+        Ok(kaomado) => Some(kaomado),
+        Err(err) => {
+            warn!(
+                "impossible to read the portraits from {:?}, they won't be displayed: {}",
+                path, err
+            );
+            None
+        }
+    }
+}
+
+/// Load the portraits when needed, and keep the one actually displayed
+#[derive(Default)]
+pub struct PortraitStore {
+    kaomado: Option<Kaomado>,
+    loaded: HashMap<(usize, usize), Option<Rc<WanHostFragment>>>,
+    /// The portrait to draw, if any
+    pub displayed: Option<Rc<WanHostFragment>>,
+}
+
+impl PortraitStore {
+    /// Create a new `PortraitStore`. Without `Kaomado`, no portrait will be displayed.
+    pub fn new(kaomado: Option<Kaomado>) -> Self {
+        Self {
+            kaomado,
+            ..Default::default()
+        }
+    }
+
+    /// Return the portrait for the emotion, or the normal portrait if this emotion doesn't exist for this species.
+    ///
+    /// Texture for the GPU are only created if a `texture_context` is given.
+    pub fn get_portrait(
+        &mut self,
+        mut texture_context: Option<&mut G2dTextureContext>,
+        entry: usize,
+        facetype: FaceType,
+    ) -> Option<Rc<WanHostFragment>> {
+        for slot in [facetype.kaomado_slot(), FaceType::NORMAL.kaomado_slot()] {
+            if let Some(portrait) = self.get_slot(texture_context.as_deref_mut(), entry, slot) {
+                return Some(portrait);
+            };
+        }
+        None
+    }
+
+    fn get_slot(
+        &mut self,
+        texture_context: Option<&mut G2dTextureContext>,
+        entry: usize,
+        slot: usize,
+    ) -> Option<Rc<WanHostFragment>> {
+        let kaomado = self.kaomado.as_ref()?;
+        self.loaded
+            .entry((entry, slot))
+            .or_insert_with(|| match kaomado.get_portrait(entry, slot) {
+                Ok(image) => {
+                    image.map(|image| Rc::new(WanHostFragment::new(image, texture_context)))
+                }
+                Err(err) => {
+                    warn!(
+                        "impossible to decode the portrait {} of the entry {}: {}",
+                        slot, entry, err
+                    );
+                    None
+                }
+            })
+            .clone()
+    }
+}
+
+/// Decode a 4 bits per pixel, 8x8 tiled portrait
+fn decode_tiled_image(pixels: &[u8], palette: &[u8]) -> RgbaImage {
+    let tiles_per_row = PORTRAIT_SIZE / TILE_SIZE;
+    RgbaImage::from_fn(PORTRAIT_SIZE, PORTRAIT_SIZE, |x, y| {
+        let tile = (y / TILE_SIZE) * tiles_per_row + x / TILE_SIZE;
+        let pixel_in_tile = (y % TILE_SIZE) * TILE_SIZE + x % TILE_SIZE;
+        let pixel = (tile * TILE_SIZE * TILE_SIZE + pixel_in_tile) as usize;
+        let byte = pixels.get(pixel / 2).copied().unwrap_or(0);
+        // the first pixel is in the low nibble
+        let color = if pixel.is_multiple_of(2) {
+            byte & 0xF
+        } else {
+            byte >> 4
+        } as usize;
+        Rgba([
+            palette[color * 3],
+            palette[color * 3 + 1],
+            palette[color * 3 + 2],
+            255,
+        ])
+    })
+}
+
+#[test]
+fn test_kaomado_decode() {
+    // a portrait where each tile is filled with the color of its index
+    let mut pixels = Vec::new();
+    for tile in 0..25u8 {
+        pixels.extend([(tile % 16) | ((tile % 16) << 4); 32]);
+    }
+    // AT4PX with only uncompressed bytes
+    let mut at4px = b"AT4PX".to_vec();
+    at4px.extend([0; 11]);
+    at4px.extend((pixels.len() as u16).to_le_bytes());
+    for chunk in pixels.chunks(8) {
+        at4px.push(0xFF);
+        at4px.extend(chunk);
+    }
+    let container_lenght = (at4px.len() as u16).to_le_bytes();
+    at4px[5] = container_lenght[0];
+    at4px[6] = container_lenght[1];
+
+    let mut content = vec![0; SLOT_NUMBER * 4 * 2];
+    let pointer = (content.len() as u32).to_le_bytes();
+    // entry 1, slot 2
+    content[(SLOT_NUMBER + 2) * 4..(SLOT_NUMBER + 2) * 4 + 4].copy_from_slice(&pointer);
+    for color in 0..16u8 {
+        content.extend([color * 16, 0, 255 - color]);
+    }
+    content.extend(at4px);
+
+    let kaomado = Kaomado::new(content);
+    assert!(kaomado.get_portrait(1, 0).unwrap().is_none());
+    assert!(kaomado.get_portrait(5, 0).unwrap().is_none());
+    let portrait = kaomado.get_portrait(1, 2).unwrap().unwrap();
+    assert_eq!(portrait.get_pixel(0, 0), &Rgba([0, 0, 255, 255]));
+    assert_eq!(portrait.get_pixel(9, 0), &Rgba([16, 0, 254, 255]));
+    assert_eq!(portrait.get_pixel(39, 39), &Rgba([128, 0, 247, 255]));
+}
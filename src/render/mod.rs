@@ -5,10 +5,40 @@ mod camera;
 pub use camera::Camera;
 
 mod wan;
-pub use wan::{WanHandler, WanSprite, WanStore};
+pub use wan::{WanHandler, WanHostFragment, WanSprite, WanStore};
 
 mod preload;
 pub use preload::{PreLoad, PreLoadState};
 
 mod charactersprite;
 pub use charactersprite::CharacterSprite;
+
+mod updatereceiver;
+pub use updatereceiver::{execute_frame, UpdateReceiver};
+
+mod spriteupdater;
+pub use spriteupdater::SpriteUpdater;
+
+mod headless;
+pub use headless::HeadlessRenderer;
+
+mod canvas;
+pub use canvas::{Canvas, PistonCanvas};
+
+mod drawscene;
+pub use drawscene::draw_scene;
+
+mod species;
+pub use species::{SpeciesEntry, SpeciesRegistry, ANIMATION_IDLE, ANIMATION_RUN, ANIMATION_WALK};
+
+mod kaomado;
+pub use kaomado::{load_kaomado, PortraitStore};
+
+mod font;
+pub use font::{draw_char, draw_text, GLYPH_ADVANCE, GLYPH_SIZE};
+
+mod textbox;
+pub use textbox::{draw_menu, draw_message};
+
+mod offline;
+pub use offline::{OfflineError, OfflineOutput, OfflineRenderer};
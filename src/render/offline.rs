@@ -0,0 +1,214 @@
+use crate::gamedata::Time;
+use crate::render::{
+    draw_scene, load_kaomado, Camera, CharacterSprite, HeadlessRenderer, PortraitStore,
+    SpeciesRegistry, SpriteUpdater, WanStore,
+};
+use crate::{LoadError, Logic, ScriptError};
+use ::image::codecs::gif::{GifEncoder, Repeat};
+use ::image::{Delay, Frame, ImageError, RgbaImage};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs::{create_dir_all, File};
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+
+/// Where an `OfflineRenderer` write the rendered frames
+pub enum OfflineOutput {
+    /// Write each frame as a png file in this folder, named frame_00000.png, frame_00001.png...
+    PngSequence(PathBuf),
+    /// Write all the frames in this animated gif file
+    Gif(PathBuf),
+}
+
+/// An error that happened while rendering a scene to files
+#[derive(Debug)]
+pub enum OfflineError {
+    /// The script failed
+    Script(ScriptError),
+    /// The rendered frames couldn't be written
+    Image(ImageError),
+}
+
+impl Error for OfflineError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Script(err) => Some(err),
+            Self::Image(err) => Some(err),
+        }
+    }
+}
+
+impl fmt::Display for OfflineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Script(err) => write!(f, "the script failed: {}", err),
+            Self::Image(err) => write!(f, "impossible to write the rendered frames: {}", err),
+        }
+    }
+}
+
+impl From<ScriptError> for OfflineError {
+    fn from(err: ScriptError) -> Self {
+        Self::Script(err)
+    }
+}
+
+impl From<ImageError> for OfflineError {
+    fn from(err: ImageError) -> Self {
+        Self::Image(err)
+    }
+}
+
+impl From<std::io::Error> for OfflineError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Image(err.into())
+    }
+}
+
+/// The time a message stay displayed once entirely shown, before being confirmed, in second
+const READING_TIME: f64 = 1.5;
+
+/// Render a cutscene to files, without any window. The scene is drawn by software, at a fixed framerate.
+/// Messages are confirmed automatically after `READING_TIME`.
+pub struct OfflineRenderer {
+    headless: HeadlessRenderer,
+    image_store: Option<WanStore<File>>,
+    species: SpeciesRegistry,
+    portraits: PortraitStore,
+    characters_sprite: HashMap<String, CharacterSprite>,
+    size: (u32, u32),
+    framerate: NonZeroU32,
+}
+
+impl OfflineRenderer {
+    /// Create a new `OfflineRenderer`, that will render frames of size pixel, with framerate frames per second
+    pub fn new(size: (u32, u32), framerate: NonZeroU32) -> Self {
+        let mut headless = HeadlessRenderer::new(Time::from_frames(1, framerate.get()));
+        headless.set_confirm_delay(Time::new(READING_TIME));
+        Self {
+            headless,
+            image_store: None,
+            species: SpeciesRegistry::default(),
+            portraits: PortraitStore::default(),
+            characters_sprite: HashMap::new(),
+            size,
+            framerate,
+        }
+    }
+
+    /// Load the script, and the sprites from data_path, the folder of an extracted explorer of sky rom
+    pub fn load<S: AsRef<[u8]> + ?Sized>(
+        &mut self,
+        code: &S,
+        data_path: &Path,
+    ) -> Result<(), LoadError> {
+        self.headless.load(code)?;
+        self.image_store = Some(WanStore::open(&data_path.join("MONSTER/m_ground.bin"))?);
+        self.portraits = PortraitStore::new(load_kaomado(data_path));
+        self.characters_sprite = HashMap::new();
+        Ok(())
+    }
+
+    /// Choose how actors are displayed. The embedded `SpeciesRegistry` is used by default.
+    pub fn set_species_registry(&mut self, species: SpeciesRegistry) {
+        self.species = species;
+    }
+
+    pub fn logic(&self) -> &Logic {
+        self.headless.logic()
+    }
+
+    /// Advance the scene by duration without rendering it, like if the script was started earlier
+    pub fn skip(&mut self, duration: Time) -> Result<(), ScriptError> {
+        let mut sprite_updater = SpriteUpdater {
+            texture_context: None,
+            image_store: self.image_store.as_mut().unwrap(),
+            species: &self.species,
+            portraits: &mut self.portraits,
+            characters_sprite: &mut self.characters_sprite,
+        };
+        let end = self.headless.elapsed().get_time() + duration.get_time();
+        while self.headless.elapsed().get_time() < end && !self.headless.is_finished() {
+            self.headless.step(&mut sprite_updater)?;
+        }
+        Ok(())
+    }
+
+    /// Render every frame of the script, until it finish or time_limit is reached.
+    /// time_limit is counted from the start of the script, including skipped time.
+    ///
+    /// Return the number of frame written.
+    pub fn render(
+        &mut self,
+        output: OfflineOutput,
+        time_limit: Option<Time>,
+    ) -> Result<usize, OfflineError> {
+        let mut gif_encoder = match &output {
+            OfflineOutput::PngSequence(folder) => {
+                create_dir_all(folder)?;
+                None
+            }
+            OfflineOutput::Gif(path) => {
+                let mut encoder = GifEncoder::new(File::create(path)?);
+                encoder.set_repeat(Repeat::Infinite)?;
+                Some(encoder)
+            }
+        };
+
+        let image_store = self.image_store.as_mut().unwrap();
+        // What does a position unit represent in pixel ? (float)
+        let mut camera = Camera::new(100.0);
+        let mut frame_number = 0;
+        loop {
+            let mut sprite_updater = SpriteUpdater {
+                texture_context: None,
+                image_store,
+                species: &self.species,
+                portraits: &mut self.portraits,
+                characters_sprite: &mut self.characters_sprite,
+            };
+            self.headless.step(&mut sprite_updater)?;
+
+            let mut canvas = RgbaImage::new(self.size.0, self.size.1);
+            {
+                let scene = self.headless.logic().scene.lock().unwrap();
+                draw_scene(
+                    &mut canvas,
+                    &scene,
+                    &mut self.characters_sprite,
+                    &self.portraits,
+                    &mut camera,
+                );
+            }
+
+            match &output {
+                OfflineOutput::PngSequence(folder) => {
+                    canvas.save(folder.join(format!("frame_{:05}.png", frame_number)))?
+                }
+                OfflineOutput::Gif(_) => {
+                    gif_encoder
+                        .as_mut()
+                        .unwrap()
+                        .encode_frame(Frame::from_parts(
+                            canvas,
+                            0,
+                            0,
+                            Delay::from_numer_denom_ms(1000, self.framerate.get()),
+                        ))?
+                }
+            };
+            frame_number += 1;
+
+            if self.headless.is_finished() {
+                break;
+            };
+            if let Some(time_limit) = time_limit {
+                if self.headless.elapsed() >= time_limit {
+                    break;
+                };
+            };
+        }
+        Ok(frame_number)
+    }
+}
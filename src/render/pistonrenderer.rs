@@ -1,132 +1,170 @@
 extern crate piston_window;
-use crate::gamedata::Update;
+use crate::gamedata::{PadButton, PadState, Time};
 use crate::render::Camera;
 use crate::render::CharacterSprite;
-use crate::render::WanStore;
+use crate::render::{
+    draw_scene, execute_frame, load_kaomado, PistonCanvas, PortraitStore, SpeciesRegistry,
+    SpriteUpdater, WanStore,
+};
 use crate::Input;
+use crate::LoadError;
 use crate::Logic;
+use crate::ScriptError;
 use piston_window::*;
-use pmd_cpack::CPack;
 use std::collections::HashMap;
 use std::fs::File;
-use std::path::PathBuf;
+use std::num::NonZeroU32;
+use std::path::Path;
+
+/// Return the 3DS button a key or mouse button is mapped to
+fn pad_button(button: &Button) -> Option<PadButton> {
+    Some(match button {
+        Button::Keyboard(Key::Z)
+        | Button::Keyboard(Key::Return)
+        | Button::Keyboard(Key::Space)
+        | Button::Mouse(MouseButton::Left) => PadButton::A,
+        Button::Keyboard(Key::X) => PadButton::B,
+        Button::Keyboard(Key::S) => PadButton::X,
+        Button::Keyboard(Key::A) => PadButton::Y,
+        Button::Keyboard(Key::Q) => PadButton::L,
+        Button::Keyboard(Key::W) => PadButton::R,
+        Button::Keyboard(Key::Tab) => PadButton::START,
+        Button::Keyboard(Key::Backspace) => PadButton::SELECT,
+        Button::Keyboard(Key::Up) => PadButton::UP,
+        Button::Keyboard(Key::Down) => PadButton::DOWN,
+        Button::Keyboard(Key::Left) => PadButton::LEFT,
+        Button::Keyboard(Key::Right) => PadButton::RIGHT,
+        _ => return None,
+    })
+}
 
 pub struct PistonRenderer {
     window: PistonWindow,
     logic: Option<Logic>,
     image_store: Option<WanStore<File>>,
+    species: SpeciesRegistry,
+    portraits: PortraitStore,
     characters_sprite: Option<HashMap<String, CharacterSprite>>,
 }
 
 impl PistonRenderer {
-    #[allow(clippy::new_without_default)]
-    pub fn new() -> Self {
+    /// Open a new window, of the given size in pixel
+    pub fn new(size: (u32, u32)) -> Self {
         PistonRenderer {
-            window: WindowSettings::new("Hello Piston!", [640, 480])
+            window: WindowSettings::new("Hello Piston!", [size.0, size.1])
                 .exit_on_esc(true)
                 .build()
                 .unwrap(),
             logic: None,
             image_store: None,
+            species: SpeciesRegistry::default(),
+            portraits: PortraitStore::default(),
             characters_sprite: Some(HashMap::new()),
         }
     }
 
-    pub fn load(&mut self, code: &str) {
-        self.logic = Some(Logic::new(code));
-        //TODO: do not hardcode the path
-        self.image_store = Some(WanStore::new(
-            CPack::new_from_file(File::open(PathBuf::from("data/MONSTER/m_ground.bin")).unwrap())
-                .unwrap(),
-        ));
+    /// Load the script, and the sprites from data_path, the folder of an extracted explorer of sky rom
+    pub fn load<S: AsRef<[u8]> + ?Sized>(
+        &mut self,
+        code: &S,
+        data_path: &Path,
+    ) -> Result<(), LoadError> {
+        self.logic = Some(Logic::new(code)?);
+        self.image_store = Some(WanStore::open(&data_path.join("MONSTER/m_ground.bin"))?);
+        self.portraits = PortraitStore::new(load_kaomado(data_path));
+        Ok(())
+    }
+
+    /// Choose how actors are displayed. The embedded `SpeciesRegistry` is used by default.
+    pub fn set_species_registry(&mut self, species: SpeciesRegistry) {
+        self.species = species;
+    }
+
+    pub fn logic(&self) -> &Logic {
+        self.logic.as_ref().unwrap()
     }
 
-    pub fn run(&mut self) {
+    pub fn logic_mut(&mut self) -> &mut Logic {
+        self.logic.as_mut().unwrap()
+    }
+
+    /// Advance the scene by duration without displaying it, like if the script was started earlier. The scene is run
+    /// in frames at framerate, like the `OfflineRenderer` does, so both reach the same state.
+    pub fn skip(&mut self, duration: Time, framerate: NonZeroU32) -> Result<(), ScriptError> {
+        let mut texture_context = self.window.create_texture_context();
+        let mut sprite_updater = SpriteUpdater {
+            texture_context: Some(&mut texture_context),
+            image_store: self.image_store.as_mut().unwrap(),
+            species: &self.species,
+            portraits: &mut self.portraits,
+            characters_sprite: self.characters_sprite.as_mut().unwrap(),
+        };
+        let logic = self.logic.as_mut().unwrap();
+        let timestep = Time::from_frames(1, framerate.get());
+        let mut elapsed = Time::new(0.0);
+        while elapsed < duration {
+            execute_frame(logic, Input::new(timestep.get_time()), &mut sprite_updater)?;
+            elapsed += timestep;
+        }
+        Ok(())
+    }
+
+    /// Display the scene until the window is closed. Return the first error raised by the script.
+    pub fn run(&mut self) -> Result<(), ScriptError> {
         let image_store = self.image_store.as_mut().unwrap();
         let logic: &mut Logic = self.logic.as_mut().unwrap();
         let characters_sprite = self.characters_sprite.as_mut().unwrap();
+        let species = &self.species;
+        let portraits = &mut self.portraits;
+        let mut texture_context = self.window.create_texture_context();
 
         // What does a position unit represent in pixel ? (float)
         let scale = 100.0;
-        let mut camera = Camera::new(scale, (0.0, 0.0), 0.0);
+        let mut camera = Camera::new(scale);
+        let mut pad = PadState::default();
         while let Some(e) = self.window.next() {
+            if let Some(button) = e.press_args().as_ref().and_then(pad_button) {
+                pad.press(button);
+            };
+            if let Some(button) = e.release_args().as_ref().and_then(pad_button) {
+                pad.release(button);
+            };
             if let Event::Loop(Loop::Update(update_arg)) = e {
-                logic.execute(Input::new(update_arg.dt));
-                for update in logic.get_and_clear_updates() {
-                    match update {
-                        Update::AddChara(charid, actor) => {
-                            let spriteid = match actor.as_str() {
-                                "KIBAGO" => 588,
-                                "TSUTAAJA" => 6,
-                                _ => 597,
-                            };
-                            let wan_sprite = image_store
-                                .get_sprite(&mut self.window.create_texture_context(), spriteid);
-                            let mut spr = CharacterSprite::new_from_wan_sprite(wan_sprite);
-                            spr.set_animation(0, true);
-                            characters_sprite.insert(charid, spr);
-                        }
-                        Update::DelChara(charid) => {
-                            characters_sprite.remove(&charid);
-                        }
-                        Update::TimeSpent(time) => {
-                            for chara in characters_sprite.values_mut() {
-                                chara.time_spent(time);
-                            }
-                        }
-                        Update::WalkTo(charid, _, _) => {
-                            characters_sprite
-                                .get_mut(&charid)
-                                .unwrap()
-                                .set_animation(0, true);
-                        }
-                        Update::StartIDLE(charid) => {
-                            characters_sprite
-                                .get_mut(&charid)
-                                .unwrap()
-                                .set_animation(0, true);
-                        }
-                        _ => (),
-                    }
-                }
+                let mut sprite_updater = SpriteUpdater {
+                    texture_context: Some(&mut texture_context),
+                    image_store,
+                    species,
+                    portraits,
+                    characters_sprite,
+                };
+                pad.set_direction_from_dpad();
+                let input = Input {
+                    time_elapsed: update_arg.dt,
+                    pad: pad.clone(),
+                    ..Input::default()
+                };
+                pad.new_frame();
+                execute_frame(logic, input, &mut sprite_updater)?;
             };
 
             let scene_arc = logic.scene.clone();
 
             self.window.draw_2d(&e, |c, g, _device| {
                 let scene = scene_arc.lock().unwrap();
-                let viewport = c.viewport.as_ref().unwrap();
-                let screen_x = viewport.draw_size[0] as f64;
-                let screen_y = viewport.draw_size[1] as f64;
-                camera.set_screen_size((screen_x, screen_y));
-                //clear the screen
-                clear([1.0; 4], g);
-                //draw characters
-                for (charaid, chara) in scene.charas.iter() {
-                    let display_data =
-                        camera.compute_display_data((chara.position.x, -chara.position.y), 0.0);
-                    characters_sprite.get_mut(charaid).unwrap().draw(
-                        g,
-                        &c,
-                        &(display_data.x_pixel, display_data.y_pixel),
-                        scale / 32.0,
-                        chara.angle,
-                    );
-                }
-                //render the front screen
-                rectangle(
-                    [
-                        scene.screens[0].actual_color.r,
-                        scene.screens[0].actual_color.g,
-                        scene.screens[0].actual_color.b,
-                        scene.screens[0].actual_color.a,
-                    ],
-                    [0.0, 0.0, screen_x, screen_y],
-                    c.transform,
-                    g,
+                let mut canvas = PistonCanvas {
+                    graphic: g,
+                    context: &c,
+                };
+                draw_scene(
+                    &mut canvas,
+                    &scene,
+                    characters_sprite,
+                    portraits,
+                    &mut camera,
                 );
             });
         }
+        Ok(())
     }
 
     pub fn close(&mut self) {}
@@ -0,0 +1,160 @@
+use crate::gamedata::{MOTION_IDLE, MOTION_RUN, MOTION_WALK};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+/// The registry embedded in the program, used when no other is given
+const DEFAULT_SPECIES: &str = include_str!("species.json");
+
+/// The name of the animation played when a character is idle
+pub const ANIMATION_IDLE: &str = MOTION_IDLE;
+/// The name of the animation played when a character walk
+pub const ANIMATION_WALK: &str = MOTION_WALK;
+/// The name of the animation played when a character run
+pub const ANIMATION_RUN: &str = MOTION_RUN;
+
+/// The animation id of the named animations in the standard explorer of sky ground sprites, used when a species doesn't
+/// override them. Explorer of sky has no run nor talk animation: the walk and the idle one are played instead.
+pub const DEFAULT_ANIMATIONS: [(&str, usize); 6] = [
+    (ANIMATION_WALK, 0),
+    (ANIMATION_RUN, 0),
+    ("sleep", 5),
+    (ANIMATION_IDLE, 7),
+    ("talk", 7),
+    ("nod", 22),
+];
+
+/// How to display a species (an actor, like ``KIBAGO``)
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SpeciesEntry {
+    /// The index of the sprite in explorer of sky ``MONSTER/m_ground.bin``
+    pub sprite: usize,
+    /// The index of the entry of this species in explorer of sky ``FONT/kaomado.kao``, if it has portraits
+    #[serde(default)]
+    pub portrait: Option<usize>,
+    /// The animation id to use for a named animation (like `ANIMATION_WALK`, or the ``talk`` motion), when it isn't the one of
+    /// `DEFAULT_ANIMATIONS`
+    #[serde(default)]
+    pub animations: HashMap<String, usize>,
+    /// ``true`` for the placeholder of the registry, drawn with a marker so it isn't mistaken for the real species
+    #[serde(skip)]
+    pub is_placeholder: bool,
+}
+
+impl SpeciesEntry {
+    /// Return the animation id to play for the named animation. Animation neither overriden nor in
+    /// `DEFAULT_ANIMATIONS` use the animation 0.
+    pub fn animation(&self, name: &str) -> usize {
+        self.animations.get(name).copied().unwrap_or_else(|| {
+            DEFAULT_ANIMATIONS
+                .iter()
+                .find(|(default_name, _)| *default_name == name)
+                .map(|(_, id)| *id)
+                .unwrap_or(0)
+        })
+    }
+}
+
+/// Map the actor names of PSMD/GTI to the sprites and portraits of explorer of sky.
+///
+/// It is read from a json file like ``{"placeholder": {"sprite": 597}, "species": {"PIKACHUU": {"sprite": 25, "portrait": 25, "animations": {"idle": 7}}}}``
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SpeciesRegistry {
+    /// Used for the species that are not in this registry
+    pub placeholder: SpeciesEntry,
+    pub species: HashMap<String, SpeciesEntry>,
+}
+
+impl Default for SpeciesRegistry {
+    /// The embedded registry. It contain the PSMD starters that exist in explorer of sky, from ``FUSHIGIDANE`` to
+    /// ``RIORU``. The others, like the ones of the fifth and sixth generations, are displayed with the placeholder.
+    fn default() -> Self {
+        Self::from_json(DEFAULT_SPECIES.as_bytes()).unwrap()
+    }
+}
+
+impl SpeciesRegistry {
+    pub fn from_json<R: Read>(reader: R) -> serde_json::Result<Self> {
+        let mut registry: Self = serde_json::from_reader(reader)?;
+        registry.placeholder.is_placeholder = true;
+        Ok(registry)
+    }
+
+    /// Read a json registry file
+    pub fn open(path: &Path) -> serde_json::Result<Self> {
+        let file = File::open(path).map_err(serde_json::Error::io)?;
+        Self::from_json(BufReader::new(file))
+    }
+
+    /// Return how to display the actor. Return the placeholder, with a warning, if it isn't in the registry.
+    pub fn get(&self, actor: &str) -> &SpeciesEntry {
+        match self.species.get(actor) {
+            Some(entry) => entry,
+            None => {
+                warn!(
+                    "the species {:?} is not in the species registry, a placeholder will be displayed",
+                    actor
+                );
+                &self.placeholder
+            }
+        }
+    }
+
+    /// Like `get`, but also return the placeholder, with a warning, if the sprite of the species isn't one of the
+    /// sprite_count sprites available. Return ``None`` if the sprite of the placeholder isn't available either.
+    pub fn get_available(&self, actor: &str, sprite_count: usize) -> Option<&SpeciesEntry> {
+        let entry = self.get(actor);
+        if entry.sprite < sprite_count {
+            return Some(entry);
+        };
+        warn!(
+            "the sprite {} of the species {:?} doesn't exist, a placeholder will be displayed",
+            entry.sprite, actor
+        );
+        if self.placeholder.sprite < sprite_count {
+            Some(&self.placeholder)
+        } else {
+            error!(
+                "the placeholder sprite {} doesn't exist, {:?} will not be displayed",
+                self.placeholder.sprite, actor
+            );
+            None
+        }
+    }
+}
+
+#[test]
+fn test_species_registry() {
+    let registry = SpeciesRegistry::default();
+    assert_eq!(registry.get("PIKACHUU").portrait, Some(25));
+    assert_eq!(registry.get("RIORU").sprite, 489);
+    assert!(!registry.get("HIKOZARU").is_placeholder);
+    // not in explorer of sky
+    assert!(registry.get("TSUTAAJA").is_placeholder);
+    assert_eq!(registry.get("UNKNOWN").sprite, 597);
+    assert!(registry.get("UNKNOWN").is_placeholder);
+
+    let registry = SpeciesRegistry::from_json(
+        "{\"placeholder\": {\"sprite\": 1}, \"species\": {\"PIKACHU\": {\"sprite\": 25, \"portrait\": 25, \"animations\": {\"idle\": 7}}}}"
+            .as_bytes(),
+    )
+    .unwrap();
+    let pikachu = registry.get("PIKACHU");
+    assert_eq!(pikachu.portrait, Some(25));
+    assert_eq!(pikachu.animation(ANIMATION_IDLE), 7);
+    assert_eq!(pikachu.animation(ANIMATION_WALK), 0);
+    assert_eq!(pikachu.animation("nod"), 22);
+    assert_eq!(pikachu.animation("unknown"), 0);
+
+    // a sprite out of the file isn't loaded
+    assert_eq!(registry.get_available("PIKACHU", 100).unwrap().sprite, 25);
+    assert!(
+        registry
+            .get_available("PIKACHU", 10)
+            .unwrap()
+            .is_placeholder
+    );
+    assert!(registry.get_available("PIKACHU", 1).is_none());
+}
@@ -0,0 +1,106 @@
+use crate::gamedata::{MoveKind, Update};
+use crate::render::{
+    CharacterSprite, PortraitStore, SpeciesRegistry, UpdateReceiver, WanStore, ANIMATION_IDLE,
+    ANIMATION_RUN, ANIMATION_WALK,
+};
+use piston_window::G2dTextureContext;
+use std::collections::HashMap;
+use std::io::{Read, Seek};
+
+/// Keep the `CharacterSprite`s of a renderer in sync with the `Update`s of the scene
+pub struct SpriteUpdater<'a, F: 'static + Read + Seek + Send> {
+    /// The texture context of the window. ``None`` for software rendering.
+    pub texture_context: Option<&'a mut G2dTextureContext>,
+    pub image_store: &'a mut WanStore<F>,
+    pub species: &'a SpeciesRegistry,
+    pub characters_sprite: &'a mut HashMap<String, CharacterSprite>,
+    pub portraits: &'a mut PortraitStore,
+}
+
+impl<F: 'static + Read + Seek + Send> UpdateReceiver for SpriteUpdater<'_, F> {
+    fn receive_update(&mut self, update: Update) {
+        match update {
+            Update::AddChara(charid, actor) => {
+                let species = match self
+                    .species
+                    .get_available(&actor, self.image_store.sprite_count())
+                {
+                    Some(species) => species,
+                    None => return,
+                };
+                let wan_sprite = self
+                    .image_store
+                    .get_sprite(self.texture_context.as_deref_mut(), species.sprite);
+                let mut spr = CharacterSprite::new_from_wan_sprite(wan_sprite, species.clone());
+                spr.play_animation(ANIMATION_IDLE);
+                self.characters_sprite.insert(charid, spr);
+            }
+            Update::DelChara(charid) => {
+                self.characters_sprite.remove(&charid);
+            }
+            Update::TimeSpent(time) => {
+                for chara in self.characters_sprite.values_mut() {
+                    chara.time_spent(time);
+                }
+            }
+            Update::WalkTo(charid, _, _) => {
+                if let Some(sprite) = self.characters_sprite.get_mut(&charid) {
+                    sprite.play_animation(ANIMATION_WALK);
+                };
+            }
+            Update::MoveAlong(charid, _, _, kind, _) => {
+                let animation = match kind {
+                    MoveKind::Walk => ANIMATION_WALK,
+                    MoveKind::Run => ANIMATION_RUN,
+                    MoveKind::Slide => return,
+                };
+                if let Some(sprite) = self.characters_sprite.get_mut(&charid) {
+                    sprite.play_animation(animation);
+                };
+            }
+            Update::StartIDLE(charid) | Update::MotionFinished(charid) => {
+                if let Some(sprite) = self.characters_sprite.get_mut(&charid) {
+                    sprite.play_animation(ANIMATION_IDLE);
+                };
+            }
+            Update::SetMotion(charid, motion) => {
+                if let Some(sprite) = self.characters_sprite.get_mut(&charid) {
+                    sprite.play_motion(&motion);
+                };
+            }
+            Update::SetPortrait(portrait) => {
+                let entry = self
+                    .characters_sprite
+                    .get(&portrait.actor)
+                    .and_then(|sprite| sprite.species().portrait);
+                self.portraits.displayed = match entry {
+                    Some(entry) => self.portraits.get_portrait(
+                        self.texture_context.as_deref_mut(),
+                        entry,
+                        portrait.facetype,
+                    ),
+                    None => {
+                        warn!(
+                            "no portrait is known for the character {:?}",
+                            portrait.actor
+                        );
+                        None
+                    }
+                };
+            }
+            Update::RemovePortrait => self.portraits.displayed = None,
+            _ => (),
+        }
+    }
+
+    fn finished_motions(&mut self) -> Option<Vec<String>> {
+        let mut finished: Vec<String> = self
+            .characters_sprite
+            .iter_mut()
+            .filter_map(|(charid, sprite)| sprite.take_finished_motion().then(|| charid.clone()))
+            .collect();
+        // in the same order whatever the order of the map
+        finished.sort();
+        Some(finished)
+    }
+}
@@ -0,0 +1,231 @@
+use crate::gamedata::{Color, Menu, Message, MessageElement};
+use crate::render::Canvas;
+use crate::render::{draw_char, draw_text, GLYPH_ADVANCE, GLYPH_SIZE};
+
+/// The size of a pixel of the font, in pixel of the 3DS screen
+const FONT_PIXEL_SIZE: f64 = 2.0;
+/// The height of a line of text, in pixel of the font
+const LINE_HEIGHT: usize = 8;
+/// The position and size of the text box on the 3DS screen (x, y, width, height)
+const TEXTBOX_RECT: [f64; 4] = [8.0, 168.0, 384.0, 64.0];
+/// The space between the border of the boxes and the text, in pixel of the 3DS screen
+const TEXTBOX_PADDING: f64 = 8.0;
+/// The number of lines of text visible at once
+const TEXTBOX_LINES: usize = 3;
+/// The height of the box containing the name of the speaker, above the text box
+const NAMEBOX_HEIGHT: f64 = 20.0;
+
+const TEXTBOX_COLOR: [f32; 4] = [0.05, 0.1, 0.25, 0.85];
+const DEFAULT_TEXT_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+/// A character of a message, with its position in the text box
+#[derive(Debug, PartialEq)]
+struct PlacedChar {
+    /// The index of the element of the message it come from
+    index: usize,
+    column: usize,
+    line: usize,
+    character: char,
+    color: Option<Color>,
+}
+
+/// Place the characters of the message in lines of at most max_columns characters, wrapping between words
+fn layout_message(elements: &[MessageElement], max_columns: usize) -> Vec<PlacedChar> {
+    let mut placed = Vec::new();
+    let mut column = 0;
+    let mut line = 0;
+    let mut color = None;
+    let mut word_start = true;
+    for (index, element) in elements.iter().enumerate() {
+        match element {
+            MessageElement::Char(' ') => {
+                word_start = true;
+                if column == 0 && line > 0 {
+                    // no space at the start of a wrapped line
+                    continue;
+                };
+            }
+            MessageElement::Char(_) => {
+                if word_start {
+                    let word_lenght = elements[index..]
+                        .iter()
+                        .filter(|element| !matches!(element, MessageElement::Color(_)))
+                        .take_while(|element| {
+                            matches!(element, MessageElement::Char(character) if *character != ' ')
+                        })
+                        .count();
+                    if column > 0 && column + word_lenght > max_columns {
+                        column = 0;
+                        line += 1;
+                    };
+                };
+                word_start = false;
+            }
+            MessageElement::NewLine => {
+                column = 0;
+                line += 1;
+                word_start = true;
+                continue;
+            }
+            MessageElement::Color(new_color) => {
+                color = new_color.clone();
+                continue;
+            }
+            MessageElement::Wait(_) | MessageElement::KeyWait => continue,
+        };
+        if let MessageElement::Char(character) = element {
+            if column >= max_columns {
+                column = 0;
+                line += 1;
+            };
+            placed.push(PlacedChar {
+                index,
+                column,
+                line,
+                character: *character,
+                color: color.clone(),
+            });
+            column += 1;
+        };
+    }
+    placed
+}
+
+/// Draw the text box with the message. scale and offset place the 3DS screen on the canvas.
+pub fn draw_message<C: Canvas>(canvas: &mut C, message: &Message, scale: f64, offset: (f64, f64)) {
+    let to_canvas = |x: f64, y: f64| (offset.0 + x * scale, offset.1 + y * scale);
+    let pixel_size = FONT_PIXEL_SIZE * scale;
+
+    if let Some(speaker) = &message.speaker {
+        let width = (speaker.chars().count() * GLYPH_ADVANCE) as f64 * FONT_PIXEL_SIZE
+            + TEXTBOX_PADDING * 2.0;
+        let (x, y) = to_canvas(TEXTBOX_RECT[0], TEXTBOX_RECT[1] - NAMEBOX_HEIGHT);
+        canvas.fill_rectangle(
+            TEXTBOX_COLOR,
+            [x, y, width * scale, (NAMEBOX_HEIGHT - 2.0) * scale],
+        );
+        let text_y = (NAMEBOX_HEIGHT - 2.0 - GLYPH_SIZE.1 as f64 * FONT_PIXEL_SIZE) / 2.0;
+        draw_text(
+            canvas,
+            speaker,
+            (x + TEXTBOX_PADDING * scale, y + text_y * scale),
+            pixel_size,
+            DEFAULT_TEXT_COLOR,
+        );
+    };
+
+    let (x, y) = to_canvas(TEXTBOX_RECT[0], TEXTBOX_RECT[1]);
+    canvas.fill_rectangle(
+        TEXTBOX_COLOR,
+        [x, y, TEXTBOX_RECT[2] * scale, TEXTBOX_RECT[3] * scale],
+    );
+
+    let max_columns = ((TEXTBOX_RECT[2] - TEXTBOX_PADDING * 2.0)
+        / (GLYPH_ADVANCE as f64 * FONT_PIXEL_SIZE)) as usize;
+    let shown = message.shown_elements().len();
+    let placed: Vec<PlacedChar> = layout_message(&message.elements, max_columns)
+        .into_iter()
+        .filter(|placed| placed.index < shown)
+        .collect();
+    // scroll so the last displayed line is visible
+    let first_line = placed
+        .last()
+        .map(|last| (last.line + 1).saturating_sub(TEXTBOX_LINES))
+        .unwrap_or(0);
+    for placed in placed.iter().filter(|placed| placed.line >= first_line) {
+        let color = match &placed.color {
+            Some(color) => [color.r, color.g, color.b, color.a],
+            None => DEFAULT_TEXT_COLOR,
+        };
+        draw_char(
+            canvas,
+            placed.character,
+            (
+                x + (TEXTBOX_PADDING + (placed.column * GLYPH_ADVANCE) as f64 * FONT_PIXEL_SIZE)
+                    * scale,
+                y + (TEXTBOX_PADDING
+                    + ((placed.line - first_line) * LINE_HEIGHT) as f64 * FONT_PIXEL_SIZE)
+                    * scale,
+            ),
+            pixel_size,
+            color,
+        );
+    }
+}
+
+/// Draw the menu, above the right side of the text box. scale and offset place the 3DS screen on the canvas.
+pub fn draw_menu<C: Canvas>(canvas: &mut C, menu: &Menu, scale: f64, offset: (f64, f64)) {
+    let line_height = LINE_HEIGHT as f64 * FONT_PIXEL_SIZE;
+    let advance = GLYPH_ADVANCE as f64 * FONT_PIXEL_SIZE;
+    let longest = menu
+        .options
+        .iter()
+        .map(|option| option.chars().count())
+        .max()
+        .unwrap_or(0);
+    // one more character for the cursor
+    let width = (longest + 1) as f64 * advance + TEXTBOX_PADDING * 2.0;
+    let height = menu.options.len() as f64 * line_height + TEXTBOX_PADDING * 2.0;
+    let right = TEXTBOX_RECT[0] + TEXTBOX_RECT[2];
+    let (x, y) = (right - width, TEXTBOX_RECT[1] - 4.0 - height);
+    canvas.fill_rectangle(
+        TEXTBOX_COLOR,
+        [
+            offset.0 + x * scale,
+            offset.1 + y * scale,
+            width * scale,
+            height * scale,
+        ],
+    );
+    for (index, option) in menu.options.iter().enumerate() {
+        let line_y = offset.1 + (y + TEXTBOX_PADDING + index as f64 * line_height) * scale;
+        let text_x = offset.0 + (x + TEXTBOX_PADDING) * scale;
+        if index == menu.cursor {
+            draw_char(
+                canvas,
+                '>',
+                (text_x, line_y),
+                FONT_PIXEL_SIZE * scale,
+                DEFAULT_TEXT_COLOR,
+            );
+        };
+        draw_text(
+            canvas,
+            option,
+            (text_x + advance * scale, line_y),
+            FONT_PIXEL_SIZE * scale,
+            DEFAULT_TEXT_COLOR,
+        );
+    }
+}
+
+#[test]
+fn test_layout_message() {
+    use crate::gamedata::parse_message;
+    let placed = layout_message(&parse_message("ab cd[CS:R]ef\ng hijkl"), 5);
+    let positions: Vec<(char, usize, usize)> = placed
+        .iter()
+        .map(|placed| (placed.character, placed.column, placed.line))
+        .collect();
+    assert_eq!(
+        positions,
+        vec![
+            ('a', 0, 0),
+            ('b', 1, 0),
+            (' ', 2, 0),
+            ('c', 0, 1),
+            ('d', 1, 1),
+            ('e', 2, 1),
+            ('f', 3, 1),
+            ('g', 0, 2),
+            (' ', 1, 2),
+            ('h', 0, 3),
+            ('i', 1, 3),
+            ('j', 2, 3),
+            ('k', 3, 3),
+            ('l', 4, 3),
+        ]
+    );
+    assert!(placed[4].color.is_none());
+    assert!(placed[5].color.is_some());
+}
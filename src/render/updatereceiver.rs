@@ -0,0 +1,46 @@
+use crate::gamedata::Update;
+use crate::Input;
+use crate::Logic;
+use crate::ScriptError;
+
+/// Something that react to the `Update`s produced by a `Logic`, like a renderer keeping its sprites in sync with the scene
+pub trait UpdateReceiver {
+    /// Called once for every `Update`, in the order they were produced
+    fn receive_update(&mut self, update: Update);
+
+    /// Return the id of the characters which motion played once finished since the last call, or ``None`` if the
+    /// animations aren't played (see `Input::finished_motions`). Called before each frame.
+    fn finished_motions(&mut self) -> Option<Vec<String>> {
+        None
+    }
+}
+
+/// Ignore every update
+impl UpdateReceiver for () {
+    fn receive_update(&mut self, _: Update) {}
+}
+
+/// Store every update, so they can be inspected later
+impl UpdateReceiver for Vec<Update> {
+    fn receive_update(&mut self, update: Update) {
+        self.push(update);
+    }
+}
+
+/// Execute a frame of `logic`, with the motions `receiver` finished playing, then give all the `Update`s it produced to `receiver`.
+///
+/// This is the common code shared by every renderer. The `Update`s produced before an error are still given to `receiver`.
+pub fn execute_frame<R: UpdateReceiver + ?Sized>(
+    logic: &mut Logic,
+    mut input: Input,
+    receiver: &mut R,
+) -> Result<(), ScriptError> {
+    if input.finished_motions.is_none() {
+        input.finished_motions = receiver.finished_motions();
+    };
+    let result = logic.execute(input);
+    for update in logic.get_and_clear_updates() {
+        receiver.receive_update(update);
+    }
+    result
+}
@@ -1,13 +1,15 @@
 extern crate piston_window;
-use crate::render::{PreLoad, PreLoadState};
-use ::image::{ImageBuffer, Rgba};
+use crate::render::{Canvas, PreLoad, PreLoadState};
+use ::image::RgbaImage;
 use piston_window::*;
-use pmd_cpack::CPack;
+use pmd_cpack::{CPack, CPackError};
 use pmd_pkdpx::{decompress_px, is_px};
 use pmd_wan::{AnimationStore, FragmentFlip, FrameStore, OamShape, WanImage as WanImg};
 use std::collections::HashMap;
+use std::fs::File;
 use std::io::Cursor;
 use std::io::{Read, Seek};
+use std::path::Path;
 use std::thread;
 use std::{rc::Rc, sync::Arc};
 
@@ -24,17 +26,22 @@ impl<F: 'static + Read + Seek + Send> WanStore<F> {
         }
         WanStore {
             pack: Arc::new(pack),
-            sprites: sprites,
+            sprites,
         }
     }
 
-    pub fn preload_sprite(&mut self, sprite_id: usize) -> () {
+    /// The number of sprites in the file. The sprite ids go from 0 to this number excluded.
+    pub fn sprite_count(&self) -> usize {
+        self.pack.len()
+    }
+
+    pub fn preload_sprite(&mut self, sprite_id: usize) {
         if sprite_id >= self.pack.len() {
             panic!("the sprite id does not exist !");
         }
         match &self.sprites[sprite_id].state {
-            PreLoadState::Loading => return (), //TODO: rather check if it finished, and if there was an error, retry it.
-            PreLoadState::Loaded => return (),
+            PreLoadState::Loading => return, //TODO: rather check if it finished, and if there was an error, retry it.
+            PreLoadState::Loaded => return,
             _ => (),
         }
         let pack = self.pack.clone();
@@ -47,35 +54,39 @@ impl<F: 'static + Read + Seek + Send> WanStore<F> {
             }
         });
         self.sprites[sprite_id].set_status_loading(handle);
-        ()
     }
 
+    /// Return the sprite with the given id, loading it if needed.
+    ///
+    /// Texture for the GPU are only created if a `texture_context` is given.
     pub fn get_sprite(
         &mut self,
-        texture_context: &mut G2dTextureContext,
+        texture_context: Option<&mut G2dTextureContext>,
         sprite_id: usize,
     ) -> Rc<WanSprite> {
         if sprite_id >= self.pack.len() {
             panic!("the sprite id does not exist !");
         };
-        match &self.sprites[sprite_id].state {
-            PreLoadState::NotLoading => self.preload_sprite(sprite_id),
-            _ => (),
+        if let PreLoadState::NotLoading = &self.sprites[sprite_id].state {
+            self.preload_sprite(sprite_id)
         };
 
-        match &self.sprites[sprite_id].state {
-            PreLoadState::Loading => {
-                let content = self.sprites[sprite_id].join();
-                self.sprites[sprite_id]
-                    .set_result(WanSprite::new_from_wan(content, texture_context));
-            }
-            _ => (),
+        if let PreLoadState::Loading = &self.sprites[sprite_id].state {
+            let content = self.sprites[sprite_id].join();
+            self.sprites[sprite_id].set_result(WanSprite::new_from_wan(content, texture_context));
         };
 
         self.sprites[sprite_id].get_result()
     }
 }
 
+impl WanStore<File> {
+    /// Open a sprite pack (like ``MONSTER/m_ground.bin``) from the file system
+    pub fn open(path: &Path) -> Result<WanStore<File>, CPackError> {
+        Ok(WanStore::new(CPack::new_from_file(File::open(path)?)?))
+    }
+}
+
 pub struct WanHandler {
     sprite: Rc<WanSprite>,
     frame_time_position: usize,
@@ -89,17 +100,17 @@ pub struct WanHandler {
 impl WanHandler {
     pub fn new(sprite: Rc<WanSprite>, with_shadow: bool) -> WanHandler {
         WanHandler {
-            sprite: sprite,
+            sprite,
             frame_time_position: 0,
             animation_variation: 0,
             animation_id: 0,
             looped_at_least_1: false,
             animation_loaded: false,
-            with_shadow: with_shadow,
+            with_shadow,
         }
     }
 
-    pub fn start_animation(&mut self, animation_id: usize, variation: usize) -> () {
+    pub fn start_animation(&mut self, animation_id: usize, variation: usize) {
         if animation_id >= self.sprite.len_animations() {
             panic!("WanHandler::start_anim: impossible to set an animation, as it doesn't exist.");
         };
@@ -110,7 +121,7 @@ impl WanHandler {
         self.frame_time_position = 0;
     }
 
-    pub fn transmute_animation(&mut self, animation_id: usize, animation_variation: usize) -> () {
+    pub fn transmute_animation(&mut self, animation_id: usize, animation_variation: usize) {
         if animation_id >= self.sprite.len_animations() {
             panic!("WanHandler::start_anim: impossible to set an animation, as it doesn't exist.");
         };
@@ -122,26 +133,27 @@ impl WanHandler {
         };
     }
 
-    pub fn draw_frame(
+    /// Draw the current frame at coord, raised by height pixels above its shadow
+    pub fn draw_frame<C: Canvas>(
         &self,
-        graphic: &mut G2d,
-        context: &Context,
+        canvas: &mut C,
         coord: &(f64, f64),
         scale: f64,
-    ) -> () {
+        height: f64,
+    ) {
         if !self.animation_loaded {
             panic!("no animation is loaded for a WanHandler !!!");
             //TODO: play the first one instead
         };
         self.sprite.draw_animation(
-            graphic,
-            &context,
+            canvas,
             self.animation_id,
             self.animation_variation,
             self.frame_time_position as u16,
             self.with_shadow,
             coord,
             scale,
+            height,
         );
     }
 
@@ -160,88 +172,40 @@ impl WanHandler {
         }
     }
 
-    #[allow(dead_code)]
     pub fn is_finished(&self) -> bool {
-        if self.looped_at_least_1 {
-            true
-        } else {
-            false
-        }
+        self.looped_at_least_1
     }
 
-    #[allow(dead_code)]
     pub fn len_animations(&self) -> usize {
         self.sprite.len_animations()
     }
 }
 
 pub struct WanHostFragment {
-    texture: G2dTexture,
-    width: f32,
-    height: f32,
+    pub image: RgbaImage,
+    pub texture: Option<G2dTexture>,
 }
 
 impl WanHostFragment {
-    fn new(
-        image: &ImageBuffer<Rgba<u8>, Vec<u8>>,
-        texture_context: &mut G2dTextureContext,
+    pub fn new(
+        image: RgbaImage,
+        texture_context: Option<&mut G2dTextureContext>,
     ) -> WanHostFragment {
-        let mut settings = TextureSettings::new();
-        settings.set_filter(Filter::Nearest);
+        let texture = texture_context.map(|texture_context| {
+            let mut settings = TextureSettings::new();
+            settings.set_filter(Filter::Nearest);
+            Texture::from_image(texture_context, &image, &settings).unwrap()
+        });
 
-        let texture = Texture::from_image(texture_context, image, &settings).unwrap();
+        WanHostFragment { image, texture }
+    }
 
-        WanHostFragment {
-            texture: texture,
-            width: image.width() as f32,
-            height: image.height() as f32,
-        }
+    pub fn width(&self) -> f64 {
+        self.image.width() as f64
     }
 
-    fn draw(
-        &self,
-        graphic: &mut G2d,
-        context: &Context,
-        coord: &(f64, f64),
-        scale: f64,
-        flip: FragmentFlip,
-    ) -> () {
-        /*if flip.0 || flip.1 {
-            canvas.get_renderer().copy_ex(
-                &self.texture,
-                None,
-                Some(Rect::new(coord.0,coord.1,(self.width*scale) as u32,(self.height*scale) as u32)),
-                0.0,
-                None,
-                flip.0,
-                flip.1,
-            ).unwrap();
-        } else {
-            canvas.get_renderer().copy(
-                &self.texture,
-                None,
-                Some(Rect::new(coord.0,coord.1,(self.width*scale) as u32,(self.height*scale) as u32)),
-            ).unwrap();
-        }*/
-        //TODO: flip
-        let scaled_width = scale * self.width as f64;
-        let scaled_height = scale * self.height as f64;
-        /*let scaled_width = 0.0;
-        let scaled_height = 0.0;*/
-        image(
-            &self.texture,
-            context
-                .transform
-                .trans(
-                    coord.0 + if flip.flip_h { scaled_width } else { 0.0 },
-                    coord.1 + if flip.flip_v { scaled_height } else { 0.0 },
-                )
-                .scale(
-                    if flip.flip_h { -scale } else { scale },
-                    if flip.flip_v { -scale } else { scale },
-                ),
-            graphic,
-        );
+    pub fn height(&self) -> f64 {
+        self.image.height() as f64
     }
 }
 
@@ -252,7 +216,10 @@ pub struct WanSprite {
 }
 
 impl WanSprite {
-    pub fn new_from_wan(wan: WanImg, texture_context: &mut G2dTextureContext) -> WanSprite {
+    pub fn new_from_wan(
+        wan: WanImg,
+        mut texture_context: Option<&mut G2dTextureContext>,
+    ) -> WanSprite {
         let mut fragment_host = HashMap::default();
         for frame in &wan.frame_store.frames {
             for fragment in &frame.fragments {
@@ -260,20 +227,13 @@ impl WanSprite {
                     fragment.fragment_bytes_index,
                     (fragment.resolution.size().x, fragment.resolution.size().y),
                 );
-                if !fragment_host.contains_key(&key) {
-                    fragment_host.insert(
-                        key,
-                        WanHostFragment::new(
-                            &wan.fragment_bytes_store.fragment_bytes[fragment.fragment_bytes_index]
-                                .get_image(
-                                    &wan.palette,
-                                    fragment.resolution.size(),
-                                    fragment.pal_idx,
-                                )
-                                .unwrap(),
-                            texture_context,
-                        ),
-                    );
+                if let std::collections::hash_map::Entry::Vacant(e) = fragment_host.entry(key) {
+                    e.insert(WanHostFragment::new(
+                        wan.fragment_bytes_store.fragment_bytes[fragment.fragment_bytes_index]
+                            .get_image(&wan.palette, fragment.resolution.size(), fragment.pal_idx)
+                            .unwrap(),
+                        texture_context.as_deref_mut(),
+                    ));
                 }
             }
         }
@@ -285,39 +245,38 @@ impl WanSprite {
         }
     }
 
-    fn draw_fragment(
+    #[allow(clippy::too_many_arguments)]
+    fn draw_fragment<C: Canvas>(
         &self,
-        graphic: &mut G2d,
-        context: &Context,
+        canvas: &mut C,
         fragment_bytes_id: usize,
         resolution: OamShape,
         coord: &(f64, f64),
         scale: f64,
         flip: FragmentFlip,
-    ) -> () {
-        self.fragment_host
+    ) {
+        let fragment = self
+            .fragment_host
             .get(&(
                 fragment_bytes_id,
                 (resolution.size().x, resolution.size().y),
             ))
-            .unwrap()
-            .draw(graphic, context, coord, scale, flip);
+            .unwrap();
+        canvas.draw_fragment(fragment, coord, scale, flip);
     }
 
-    fn draw_frame(
+    fn draw_frame<C: Canvas>(
         &self,
-        graphic: &mut G2d,
-        context: &Context,
+        canvas: &mut C,
         frame_id: usize,
         coord: &(f64, f64),
         scale: f64,
-    ) -> () {
+    ) {
         for fragment in &self.frames.frames[frame_id].fragments {
-            let offset_x = ((fragment.offset_x as f64) * scale) as f64;
-            let offset_y = ((fragment.offset_y as f64) * scale) as f64;
+            let offset_x = (fragment.offset_x as f64) * scale;
+            let offset_y = (fragment.offset_y as f64) * scale;
             self.draw_fragment(
-                graphic,
-                context,
+                canvas,
                 fragment.fragment_bytes_index,
                 fragment.resolution,
                 &(coord.0 + offset_x, coord.1 + offset_y),
@@ -327,17 +286,18 @@ impl WanSprite {
         }
     }
 
-    pub fn draw_animation(
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_animation<C: Canvas>(
         &self,
-        graphic: &mut G2d,
-        context: &Context,
+        canvas: &mut C,
         animation_id: usize,
         animation_variation: usize,
         frame_time_position: u16,
         with_shadow: bool,
         coord: &(f64, f64),
         scale: f64,
-    ) -> () {
+        height: f64,
+    ) {
         //TODO: support defining the orientation
         if animation_id >= self.animations.anim_groups.len() {
             panic!("the animation id is superior to the number of animation");
@@ -357,16 +317,15 @@ impl WanSprite {
         match animation_frame {
             None => panic!("the frame number is too high, and is not found in the animation."),
             Some(animation_frame) => {
-                let coord_x = coord.0 + (((animation_frame.offset_x as f64) * scale) as f64);
-                let coord_y = coord.1 + (((animation_frame.offset_y as f64) * scale) as f64);
+                let coord_x = coord.0 + ((animation_frame.offset_x as f64) * scale);
+                // the shadow stay on the ground
+                let coord_y = coord.1 + ((animation_frame.offset_y as f64) * scale) - height;
                 //draw shadow
                 if with_shadow {
-                    let shadow_x =
-                        coord.0 + ((animation_frame.shadow_offset_x as f64) * scale) as f64;
-                    let shadow_y =
-                        coord.1 + ((animation_frame.shadow_offset_y as f64) * scale) as f64;
-                    let shadow_coeff = (10.0 * scale) as f64;
-                    rectangle(
+                    let shadow_x = coord.0 + ((animation_frame.shadow_offset_x as f64) * scale);
+                    let shadow_y = coord.1 + ((animation_frame.shadow_offset_y as f64) * scale);
+                    let shadow_coeff = 10.0 * scale;
+                    canvas.fill_rectangle(
                         [0.0, 0.0, 0.0, 1.0],
                         [
                             shadow_x - (shadow_coeff),
@@ -374,13 +333,10 @@ impl WanSprite {
                             shadow_coeff * 2.0,
                             shadow_coeff * 2.0,
                         ],
-                        context.transform,
-                        graphic,
                     );
                 };
                 self.draw_frame(
-                    graphic,
-                    context,
+                    canvas,
                     animation_frame.frame_id as usize,
                     &(coord_x, coord_y),
                     scale,
@@ -400,10 +356,10 @@ impl WanSprite {
         if animation_variation >= self.animations.anim_groups[animation_id].len() {
             panic!("impossible to get the lenght of animation, as the given animation variation does not exist.");
         }
-        return self.animations.anim_groups[animation_id][animation_variation]
+        self.animations.anim_groups[animation_id][animation_variation]
             .frames
             .iter()
             .map(|x| x.duration as usize)
-            .sum();
+            .sum()
     }
 }
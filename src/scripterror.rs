@@ -0,0 +1,113 @@
+use std::error::Error;
+use std::fmt;
+
+/// The name given to the chunk of the loaded script. Lua use it to report the position of an error, like ``script:12: attempt to call a nil value``.
+pub const SCRIPT_CHUNK_NAME: &str = "script";
+
+/// What to do when a lua ``Thread`` fail
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ErrorPolicy {
+    /// Kill only the failing ``Thread``. The other continue to run.
+    KillTask,
+    /// Kill every ``Thread``, ending the scene
+    #[default]
+    AbortScene,
+}
+
+/// An error that happened while loading or running a lua script
+#[derive(Debug, Clone)]
+pub struct ScriptError {
+    /// The id of the task (``Thread``) that failed. ``None`` if the error happened while loading the script.
+    pub task_id: Option<u64>,
+    /// The error message, without the traceback
+    pub message: String,
+    /// The line of the script where the error happened, if known
+    pub line: Option<usize>,
+    /// The lua traceback, if any
+    pub traceback: Option<String>,
+}
+
+impl ScriptError {
+    /// Create a new `ScriptError` from an error returned by mlua
+    pub fn from_lua_error(err: &mlua::Error, task_id: Option<u64>) -> Self {
+        let (message, traceback) = match err {
+            mlua::Error::SyntaxError { message, .. } => (message.clone(), None),
+            mlua::Error::RuntimeError(content) => {
+                match content.split_once("\nstack traceback:\n") {
+                    Some((message, traceback)) => {
+                        (message.to_string(), Some(traceback.to_string()))
+                    }
+                    None => (content.clone(), None),
+                }
+            }
+            mlua::Error::CallbackError { traceback, cause } => {
+                (cause.to_string(), Some(traceback.clone()))
+            }
+            other => (other.to_string(), None),
+        };
+        let line = find_script_line(&message)
+            .or_else(|| traceback.as_ref().and_then(|tb| find_script_line(tb)));
+        Self {
+            task_id,
+            message,
+            line,
+            traceback,
+        }
+    }
+
+    /// Create a new `ScriptError` from a message, for error detected outside of lua
+    pub fn new(message: String, task_id: Option<u64>) -> Self {
+        Self {
+            task_id,
+            message,
+            line: None,
+            traceback: None,
+        }
+    }
+}
+
+/// Find the first reference to a line of the script, in the form ``script:12:``
+fn find_script_line(text: &str) -> Option<usize> {
+    let pattern = format!("{}:", SCRIPT_CHUNK_NAME);
+    let mut rest = text;
+    while let Some(position) = rest.find(&pattern) {
+        rest = &rest[position + pattern.len()..];
+        let number: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+        if !number.is_empty() && rest[number.len()..].starts_with(':') {
+            return number.parse().ok();
+        };
+    }
+    None
+}
+
+impl Error for ScriptError {}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.task_id {
+            Some(task_id) => write!(f, "error in task {}", task_id)?,
+            None => write!(f, "error while loading the script")?,
+        };
+        if let Some(line) = self.line {
+            write!(f, " at line {}", line)?;
+        };
+        write!(f, ": {}", self.message)?;
+        if let Some(traceback) = &self.traceback {
+            write!(f, "\nstack traceback:\n{}", traceback)?;
+        };
+        Ok(())
+    }
+}
+
+#[test]
+fn test_script_error_line() {
+    let err = mlua::Error::RuntimeError(
+        "script:3: boom\nstack traceback:\n\t[C]: in function 'error'\n\tscript:3: in function 'foo'"
+            .into(),
+    );
+    let script_error = ScriptError::from_lua_error(&err, Some(2));
+    assert_eq!(script_error.line, Some(3));
+    assert_eq!(script_error.message, "script:3: boom");
+    assert_eq!(script_error.task_id, Some(2));
+    assert!(script_error.traceback.unwrap().contains("function 'foo'"));
+}
@@ -0,0 +1,439 @@
+//! Find and read the cutscene scripts of an extracted romfs of pokemon super mystery dungeon or gate to infinity
+use crate::SCRIPT_CHUNK_NAME;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The first bytes of a lua 5.1 bytecode file
+const BYTECODE_SIGNATURE: &[u8] = b"\x1bLua\x51";
+/// Size of the header of a lua 5.1 bytecode file
+const BYTECODE_HEADER_SIZE: usize = 12;
+
+/// An error that happened while looking for or reading a script
+#[derive(Debug)]
+pub enum ScriptSourceError {
+    /// No script exist for this scene
+    NotFound(String),
+    /// The script file couldn't be read
+    Io(io::Error),
+    /// The file is lua bytecode this program can't understand
+    Bytecode(String),
+}
+
+impl Error for ScriptSourceError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ScriptSourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound(name) => write!(f, "no script found for the scene {:?}", name),
+            Self::Io(err) => write!(f, "impossible to read the script: {}", err),
+            Self::Bytecode(reason) => write!(f, "unsupported lua bytecode: {}", reason),
+        }
+    }
+}
+
+impl From<io::Error> for ScriptSourceError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// The content of a script, either lua source code or lua 5.1 bytecode
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptCode {
+    Source(String),
+    /// Bytecode, already converted to the layout of the running computer
+    Bytecode(Vec<u8>),
+}
+
+impl ScriptCode {
+    /// Detect if the content is source or bytecode. Bytecode compiled for another architecture (like the 3DS) is converted.
+    pub fn from_bytes(content: Vec<u8>) -> Result<Self, ScriptSourceError> {
+        if content.starts_with(BYTECODE_SIGNATURE) {
+            Ok(Self::Bytecode(convert_bytecode(&content)?))
+        } else {
+            match String::from_utf8(content) {
+                Ok(source) => Ok(Self::Source(source)),
+                Err(err) => Err(ScriptSourceError::Io(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    err,
+                ))),
+            }
+        }
+    }
+
+    /// Read a script file
+    pub fn open(path: &Path) -> Result<Self, ScriptSourceError> {
+        Self::from_bytes(fs::read(path)?)
+    }
+
+    pub fn is_bytecode(&self) -> bool {
+        matches!(self, Self::Bytecode(_))
+    }
+}
+
+impl AsRef<[u8]> for ScriptCode {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::Source(source) => source.as_bytes(),
+            Self::Bytecode(bytecode) => bytecode,
+        }
+    }
+}
+
+/// The scripts contained in an extracted romfs
+pub struct RomfsScriptSource {
+    root: PathBuf,
+}
+
+impl RomfsScriptSource {
+    /// Open an extracted romfs. Scripts are searched in its ``script`` folder, or in the whole folder if it doesn't have one.
+    pub fn new(romfs_path: &Path) -> Self {
+        let script_folder = romfs_path.join("script");
+        Self {
+            root: if script_folder.is_dir() {
+                script_folder
+            } else {
+                romfs_path.to_path_buf()
+            },
+        }
+    }
+
+    /// Return the path of the script of the scene, a file named after the scene with the ``lua`` or ``luac`` extension.
+    /// If there are multiple such file, the less nested one is choosen.
+    pub fn find_scene(&self, scene_name: &str) -> Result<Option<PathBuf>, ScriptSourceError> {
+        let mut folders = vec![self.root.clone()];
+        // breadth first, so the less nested file is found first
+        while !folders.is_empty() {
+            let mut next_folders = Vec::new();
+            for folder in folders {
+                let mut entries = fs::read_dir(&folder)?.collect::<Result<Vec<_>, _>>()?;
+                entries.sort_by_key(|entry| entry.file_name());
+                for entry in entries {
+                    let path = entry.path();
+                    if entry.file_type()?.is_dir() {
+                        next_folders.push(path);
+                    } else if is_script_of_scene(&path, scene_name) {
+                        return Ok(Some(path));
+                    };
+                }
+            }
+            folders = next_folders;
+        }
+        Ok(None)
+    }
+
+    /// Find and read the script of the scene
+    pub fn load_scene(&self, scene_name: &str) -> Result<ScriptCode, ScriptSourceError> {
+        match self.find_scene(scene_name)? {
+            Some(path) => ScriptCode::open(&path),
+            None => Err(ScriptSourceError::NotFound(scene_name.to_string())),
+        }
+    }
+}
+
+fn is_script_of_scene(path: &Path, scene_name: &str) -> bool {
+    let extension_match = path
+        .extension()
+        .map(|extension| extension == "lua" || extension == "luac")
+        .unwrap_or(false);
+    extension_match
+        && path
+            .file_stem()
+            .map(|stem| stem.eq_ignore_ascii_case(scene_name))
+            .unwrap_or(false)
+}
+
+/// Read a lua 5.1 bytecode file, with the layout described in its header
+struct BytecodeReader<'a> {
+    content: &'a [u8],
+    position: usize,
+    little_endian: bool,
+    size_t_size: usize,
+}
+
+impl BytecodeReader<'_> {
+    fn take(&mut self, lenght: usize) -> Result<&[u8], ScriptSourceError> {
+        if self.position + lenght > self.content.len() {
+            return Err(ScriptSourceError::Bytecode("the file is truncated".into()));
+        };
+        let result = &self.content[self.position..self.position + lenght];
+        self.position += lenght;
+        Ok(result)
+    }
+
+    fn read_unsigned(&mut self, size: usize) -> Result<u64, ScriptSourceError> {
+        let little_endian = self.little_endian;
+        let bytes = self.take(size)?;
+        let mut value = 0;
+        for index in 0..size {
+            let byte = if little_endian {
+                bytes[size - 1 - index]
+            } else {
+                bytes[index]
+            };
+            value = (value << 8) | byte as u64;
+        }
+        Ok(value)
+    }
+
+    fn read_int(&mut self) -> Result<u32, ScriptSourceError> {
+        Ok(self.read_unsigned(4)? as u32)
+    }
+
+    fn read_byte(&mut self) -> Result<u8, ScriptSourceError> {
+        Ok(self.take(1)?[0])
+    }
+}
+
+/// Write a lua 5.1 bytecode file, with the layout of the running computer
+struct BytecodeWriter {
+    content: Vec<u8>,
+}
+
+impl BytecodeWriter {
+    fn write_unsigned(&mut self, value: u64, size: usize) {
+        let bytes = value.to_be_bytes();
+        let bytes = &bytes[8 - size..];
+        if cfg!(target_endian = "little") {
+            self.content.extend(bytes.iter().rev());
+        } else {
+            self.content.extend(bytes);
+        }
+    }
+
+    fn write_int(&mut self, value: u32) {
+        self.write_unsigned(value as u64, 4);
+    }
+
+    fn write_byte(&mut self, value: u8) {
+        self.content.push(value);
+    }
+}
+
+/// Convert lua 5.1 bytecode to the layout of the running computer, so it can be loaded.
+///
+/// Scripts of the 3DS games are compiled for a 32 bits computer, while lua refuse to load bytecode whose
+/// integer sizes differ from its own. The name of the main function is also replaced by `SCRIPT_CHUNK_NAME`,
+/// so errors are reported the same way as for source scripts.
+pub fn convert_bytecode(content: &[u8]) -> Result<Vec<u8>, ScriptSourceError> {
+    if content.len() < BYTECODE_HEADER_SIZE || !content.starts_with(BYTECODE_SIGNATURE) {
+        return Err(ScriptSourceError::Bytecode(
+            "this is not lua 5.1 bytecode".into(),
+        ));
+    };
+    let header = &content[..BYTECODE_HEADER_SIZE];
+    let (format, endianness, int_size, size_t_size, instruction_size, number_size, integral) = (
+        header[5], header[6], header[7], header[8], header[9], header[10], header[11],
+    );
+    if format != 0 {
+        return Err(ScriptSourceError::Bytecode(format!(
+            "unknown format {}",
+            format
+        )));
+    };
+    if int_size != 4 || instruction_size != 4 || number_size != 8 || integral != 0 {
+        return Err(ScriptSourceError::Bytecode(format!(
+            "unsupported int size {}, instruction size {} or number type ({} bytes, integral: {})",
+            int_size, instruction_size, number_size, integral
+        )));
+    };
+    if size_t_size != 4 && size_t_size != 8 {
+        return Err(ScriptSourceError::Bytecode(format!(
+            "unsupported size_t size {}",
+            size_t_size
+        )));
+    };
+
+    let mut reader = BytecodeReader {
+        content,
+        position: BYTECODE_HEADER_SIZE,
+        little_endian: endianness == 1,
+        size_t_size: size_t_size as usize,
+    };
+    let mut writer = BytecodeWriter {
+        content: Vec::with_capacity(content.len() * 2),
+    };
+    writer.content.extend(BYTECODE_SIGNATURE);
+    writer.content.extend([
+        0,
+        cfg!(target_endian = "little") as u8,
+        4,
+        std::mem::size_of::<usize>() as u8,
+        4,
+        8,
+        0,
+    ]);
+    convert_function(&mut reader, &mut writer, true)?;
+    Ok(writer.content)
+}
+
+fn convert_string(
+    reader: &mut BytecodeReader,
+    writer: &mut BytecodeWriter,
+) -> Result<(), ScriptSourceError> {
+    let lenght = reader.read_unsigned(reader.size_t_size)?;
+    writer.write_unsigned(lenght, std::mem::size_of::<usize>());
+    writer.content.extend(reader.take(lenght as usize)?);
+    Ok(())
+}
+
+fn convert_function(
+    reader: &mut BytecodeReader,
+    writer: &mut BytecodeWriter,
+    is_main: bool,
+) -> Result<(), ScriptSourceError> {
+    // source name
+    if is_main {
+        let lenght = reader.read_unsigned(reader.size_t_size)?;
+        reader.take(lenght as usize)?;
+        let name = format!("={}\0", SCRIPT_CHUNK_NAME);
+        writer.write_unsigned(name.len() as u64, std::mem::size_of::<usize>());
+        writer.content.extend(name.as_bytes());
+    } else {
+        convert_string(reader, writer)?;
+    };
+    // line defined, last line defined
+    for _ in 0..2 {
+        let value = reader.read_int()?;
+        writer.write_int(value);
+    }
+    // upvalue number, parameter number, is vararg, max stack size
+    for _ in 0..4 {
+        let value = reader.read_byte()?;
+        writer.write_byte(value);
+    }
+    // code
+    let instruction_number = reader.read_int()?;
+    writer.write_int(instruction_number);
+    for _ in 0..instruction_number {
+        let instruction = reader.read_int()?;
+        writer.write_int(instruction);
+    }
+    // constants
+    let constant_number = reader.read_int()?;
+    writer.write_int(constant_number);
+    for _ in 0..constant_number {
+        let constant_type = reader.read_byte()?;
+        writer.write_byte(constant_type);
+        match constant_type {
+            // nil
+            0 => (),
+            // boolean
+            1 => {
+                let value = reader.read_byte()?;
+                writer.write_byte(value);
+            }
+            // number
+            3 => {
+                let value = reader.read_unsigned(8)?;
+                writer.write_unsigned(value, 8);
+            }
+            // string
+            4 => convert_string(reader, writer)?,
+            other => {
+                return Err(ScriptSourceError::Bytecode(format!(
+                    "unknown constant type {}",
+                    other
+                )))
+            }
+        }
+    }
+    // functions defined inside this one
+    let function_number = reader.read_int()?;
+    writer.write_int(function_number);
+    for _ in 0..function_number {
+        convert_function(reader, writer, false)?;
+    }
+    // debug informations: lines of the instructions
+    let line_number = reader.read_int()?;
+    writer.write_int(line_number);
+    for _ in 0..line_number {
+        let line = reader.read_int()?;
+        writer.write_int(line);
+    }
+    // local variables, with name, start and end
+    let local_number = reader.read_int()?;
+    writer.write_int(local_number);
+    for _ in 0..local_number {
+        convert_string(reader, writer)?;
+        for _ in 0..2 {
+            let value = reader.read_int()?;
+            writer.write_int(value);
+        }
+    }
+    // upvalue names
+    let upvalue_number = reader.read_int()?;
+    writer.write_int(upvalue_number);
+    for _ in 0..upvalue_number {
+        convert_string(reader, writer)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_convert_host_bytecode() {
+    use crate::RunningLua;
+    let lua = mlua::Lua::new();
+    let bytecode: mlua::String = lua
+        .load("local dump = string.dump(function()\n  local a = 1\n  error(\"boom\")\nend) return dump")
+        .eval()
+        .unwrap();
+    let code = ScriptCode::from_bytes(bytecode.as_bytes().to_vec()).unwrap();
+    assert!(code.is_bytecode());
+    let mut runninglua = RunningLua::new_from_script(&code).unwrap();
+    let err = runninglua.execute().unwrap_err();
+    assert_eq!(err.line, Some(3));
+}
+
+#[test]
+fn test_convert_32_bits_bytecode() {
+    // "return 42", compiled for a 32 bits little endian computer
+    let mut bytecode = b"\x1bLua\x51\x00\x01\x04\x04\x04\x08\x00".to_vec();
+    bytecode.extend(b"\x08\x00\x00\x00@42.lua\x00");
+    bytecode.extend([0; 8]); // line defined, last line defined
+    bytecode.extend([0, 0, 2, 2]); // upvalues, parameters, is vararg, max stack size
+    bytecode.extend([3, 0, 0, 0]);
+    bytecode.extend(1u32.to_le_bytes()); // LOADK 0 0
+    bytecode.extend(0x0100_001Eu32.to_le_bytes()); // RETURN 0 2
+    bytecode.extend(0x0080_001Eu32.to_le_bytes()); // RETURN 0 1
+    bytecode.extend([1, 0, 0, 0, 3]);
+    bytecode.extend(42.0f64.to_le_bytes());
+    bytecode.extend([0; 16]); // functions, lines, locals, upvalues
+
+    let code = ScriptCode::from_bytes(bytecode).unwrap();
+    let lua = mlua::Lua::new();
+    let result: u32 = lua.load(code.as_ref()).eval().unwrap();
+    assert_eq!(result, 42);
+
+    assert!(ScriptCode::from_bytes(b"\x1bLua\x51\x00\x01\x04\x04".to_vec()).is_err());
+}
+
+#[test]
+fn test_romfs_find_scene() {
+    let romfs = std::env::temp_dir().join(format!("yammy_test_romfs_{}", std::process::id()));
+    let scene_folder = romfs.join("script").join("event").join("m01_010");
+    fs::create_dir_all(&scene_folder).unwrap();
+    fs::write(scene_folder.join("m01_010.lua"), "a = 1").unwrap();
+    fs::write(romfs.join("script").join("other.lua"), "a = 2").unwrap();
+
+    let source = RomfsScriptSource::new(&romfs);
+    assert_eq!(
+        source.load_scene("m01_010").unwrap(),
+        ScriptCode::Source("a = 1".into())
+    );
+    assert!(matches!(
+        source.load_scene("m01_020"),
+        Err(ScriptSourceError::NotFound(_))
+    ));
+    fs::remove_dir_all(&romfs).unwrap();
+}
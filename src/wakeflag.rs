@@ -0,0 +1,121 @@
+//! Event driven waking of the lua ``Thread``s
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::mem::take;
+use std::sync::{Arc, Mutex};
+
+/// The key of a ``Thread`` in the set of ``Thread``s to resume. They are ordered as they should be resumed: highest
+/// priority first, then in creation order (by id).
+pub(crate) type TaskKey = (Reverse<i64>, u64);
+
+/// The ``Thread``s that were woken, and should be resumed
+pub(crate) type ReadyTasks = Arc<Mutex<BTreeSet<TaskKey>>>;
+
+#[derive(Default)]
+struct WakeFlagState {
+    woken: bool,
+    cancelled: bool,
+    /// The ``Thread``s waiting for this flag, with the set to put them in once woken
+    waiters: Vec<(TaskKey, ReadyTasks)>,
+}
+
+/// Something a ``Thread`` can wait for, like the end of a movement. Once woken, it stay so.
+///
+/// The ``Thread``s waiting for it are scheduled directly when it is woken, so the scheduler doesn't have to check
+/// the waiting ``Thread``s each frame.
+#[derive(Clone, Default)]
+pub struct WakeFlag(Arc<Mutex<WakeFlagState>>);
+
+impl WakeFlag {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Wake the flag, scheduling every ``Thread`` waiting for it
+    pub fn wake(&self) {
+        let waiters = {
+            let mut state = self.0.lock().unwrap();
+            state.woken = true;
+            take(&mut state.waiters)
+        };
+        for (key, ready) in waiters {
+            ready.lock().unwrap().insert(key);
+        }
+    }
+
+    /// Return ``true`` if the flag was woken
+    pub fn is_woken(&self) -> bool {
+        self.0.lock().unwrap().woken
+    }
+
+    /// Tell that the ``Thread`` doesn't wait for this flag anymore, like for the part of a `LockReason::Any` that
+    /// wasn't released first. The other ``Thread``s waiting for it, like many waiting for the same task, still are.
+    /// Once nobody wait for it, the flag is cancelled, so what would wake it can be stopped. Does nothing if it was
+    /// already woken.
+    pub(crate) fn cancel(&self, key: TaskKey) {
+        let mut state = self.0.lock().unwrap();
+        if !state.woken {
+            state.waiters.retain(|(waiter, _)| *waiter != key);
+            state.cancelled = state.waiters.is_empty();
+        };
+    }
+
+    /// Return ``true`` if the flag was cancelled, and nobody started waiting for it since
+    pub fn is_cancelled(&self) -> bool {
+        self.0.lock().unwrap().cancelled
+    }
+
+    /// Schedule the ``Thread`` once the flag is woken, or immediately if it already is
+    pub(crate) fn add_waiter(&self, key: TaskKey, ready: &ReadyTasks) {
+        let mut state = self.0.lock().unwrap();
+        if state.woken {
+            drop(state);
+            ready.lock().unwrap().insert(key);
+        } else {
+            state.cancelled = false;
+            state.waiters.push((key, ready.clone()));
+        }
+    }
+}
+
+impl fmt::Debug for WakeFlag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.0.lock().unwrap();
+        f.debug_struct("WakeFlag")
+            .field("woken", &state.woken)
+            .field("cancelled", &state.cancelled)
+            .finish()
+    }
+}
+
+#[test]
+fn test_wake_flag() {
+    let ready = ReadyTasks::default();
+    let flag = WakeFlag::new();
+    flag.add_waiter((Reverse(0), 2), &ready);
+    flag.add_waiter((Reverse(1), 3), &ready);
+    assert!(ready.lock().unwrap().is_empty());
+    flag.wake();
+    assert!(flag.is_woken());
+    let woken: Vec<u64> = ready.lock().unwrap().iter().map(|key| key.1).collect();
+    assert_eq!(woken, vec![3, 2]);
+    // waiting for an already woken flag schedule immediately
+    flag.add_waiter((Reverse(0), 5), &ready);
+    assert_eq!(ready.lock().unwrap().len(), 3);
+
+    // only the cancelled waiter stop waiting
+    let ready = ReadyTasks::default();
+    let flag = WakeFlag::new();
+    flag.add_waiter((Reverse(0), 2), &ready);
+    flag.add_waiter((Reverse(0), 3), &ready);
+    flag.cancel((Reverse(0), 2));
+    assert!(!flag.is_cancelled());
+    flag.cancel((Reverse(0), 3));
+    assert!(flag.is_cancelled());
+    flag.add_waiter((Reverse(0), 4), &ready);
+    assert!(!flag.is_cancelled());
+    flag.wake();
+    let woken: Vec<u64> = ready.lock().unwrap().iter().map(|key| key.1).collect();
+    assert_eq!(woken, vec![4]);
+}